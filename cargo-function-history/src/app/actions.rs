@@ -17,12 +17,13 @@ pub enum Action {
     ForwardCommit,
     BackFile,
     ForwardFile,
+    CancelSearch,
 }
 
 impl Action {
     /// All available actions
     pub fn iterator() -> Iter<'static, Action> {
-        static ACTIONS: [Action; 8] = [
+        static ACTIONS: [Action; 9] = [
             Action::Quit,
             Action::TextEdit,
             Action::ScrollUp,
@@ -31,6 +32,7 @@ impl Action {
             Action::ForwardCommit,
             Action::BackFile,
             Action::ForwardFile,
+            Action::CancelSearch,
         ];
         ACTIONS.iter()
     }
@@ -46,6 +48,7 @@ impl Action {
             Action::ForwardCommit => &[Key::Right, Key::Char('l')],
             Action::BackFile => &[Key::Shiftleft, Key::Char('H'), Key::Shift('h')],
             Action::ForwardFile => &[Key::Shiftright, Key::Char('L'), Key::Shift('l')],
+            Action::CancelSearch => &[Key::Char('x')],
         }
     }
 }
@@ -65,6 +68,7 @@ impl Display for Action {
                 Action::ForwardCommit => "ForwardCommit",
                 Action::BackFile => "BackFile",
                 Action::ForwardFile => "ForwardFile",
+                Action::CancelSearch => "CancelSearch",
             }
         )
     }
@@ -4,7 +4,9 @@ use crate::{app::actions::Action, keys::Key};
 use function_history_backend_thread::types::{
     CommandResult, FilterType, FullCommand, ListType, SearchType, Status,
 };
-use git_function_history::{FileFilterType, Filter};
+use git_function_history::{
+    CancellationToken, FileFilterType, Filter, MergeMode, Pickaxe, SearchOptions,
+};
 use ratatui::{
     style::Modifier,
     widgets::{Block, Borders, ScrollbarState},
@@ -41,6 +43,8 @@ pub struct App<'a> {
         mpsc::Receiver<(CommandResult, Status)>,
     ),
     status: Status,
+    // cancels the last search that was started
+    cancel: CancellationToken,
     pub history: Vec<String>,
     pub history_index: usize,
 }
@@ -55,6 +59,66 @@ macro_rules! unwrap_set_error {
         }
     }; // does the same thing but takes a closure to call when is some
 }
+
+/// Combines the filters of a command: filters are and-ed together, `or` starts a new
+/// group of filters, and `not` negates the filter after it.
+#[derive(Default)]
+struct FilterBuilder {
+    groups: Vec<Vec<Filter>>,
+    negate: bool,
+}
+
+impl FilterBuilder {
+    fn push(&mut self, filter: Filter) {
+        let filter = if std::mem::take(&mut self.negate) {
+            !filter
+        } else {
+            filter
+        };
+        match self.groups.last_mut() {
+            Some(group) => group.push(filter),
+            None => self.groups.push(vec![filter]),
+        }
+    }
+
+    fn not(&mut self) {
+        self.negate = !self.negate;
+    }
+
+    fn or(&mut self) -> Result<(), &'static str> {
+        if self.groups.last().is_none_or(Vec::is_empty) || self.negate {
+            return Err("No filter given before or");
+        }
+        self.groups.push(vec![]);
+        Ok(())
+    }
+
+    fn build(self) -> Result<Filter, &'static str> {
+        if self.negate {
+            return Err("No filter given after not");
+        }
+        if self.groups.last().is_some_and(Vec::is_empty) {
+            return Err("No filter given after or");
+        }
+        let mut groups = self
+            .groups
+            .into_iter()
+            .map(|mut group| {
+                if group.len() == 1 {
+                    group.remove(0)
+                } else {
+                    Filter::And(group)
+                }
+            })
+            .collect::<Vec<_>>();
+        Ok(match groups.len() {
+            0 => Filter::None,
+            1 => groups.remove(0),
+            _ => Filter::Or(groups),
+        })
+    }
+}
+
 impl App<'_> {
     #[allow(clippy::new_without_default)]
     pub fn new(
@@ -85,6 +149,7 @@ impl App<'_> {
             Action::ForwardCommit,
             Action::BackFile,
             Action::ForwardFile,
+            Action::CancelSearch,
         ]
         .into();
         let state = AppState::initialized();
@@ -106,6 +171,7 @@ impl App<'_> {
             body_height: 0,
             channels,
             status,
+            cancel: CancellationToken::new(),
             history_index: history.len() - 1,
             history,
             scroll_state: ScrollbarState::default(),
@@ -174,6 +240,11 @@ impl App<'_> {
                     }
                     AppReturn::Continue
                 }
+                Action::CancelSearch => {
+                    log::info!("cancel search");
+                    self.cancel.cancel();
+                    AppReturn::Continue
+                }
             }
         } else {
             AppReturn::Continue
@@ -196,6 +267,22 @@ impl App<'_> {
         &self.cmd_output
     }
 
+    /// starts a search (that can be cancelled with `Action::CancelSearch`)
+    pub fn search(&mut self, mut search: SearchType) {
+        search.options.cancel = Some(self.new_search());
+        self.status = Status::Loading;
+        self.channels
+            .0
+            .send(FullCommand::Search(search))
+            .expect("could not send message in thread");
+    }
+
+    // gives a new token for the next search, the last search can no longer be cancelled
+    fn new_search(&mut self) -> CancellationToken {
+        self.cancel = CancellationToken::new();
+        self.cancel.clone()
+    }
+
     pub fn run_command(&mut self) {
         let command = self.parse_command(&self.input_buffer());
         if let Some(command) = command {
@@ -218,7 +305,14 @@ impl App<'_> {
                         thing: self.cmd_output.clone(),
                         filter: self.parse_filter(iter)?,
                     })),
-                    "list" => Some(FullCommand::List(self.parse_list(iter)?)),
+                    "list" => {
+                        let (list_type, options) = self.parse_list(iter)?;
+                        Some(FullCommand::List(list_type, options))
+                    }
+                    "goto" => {
+                        self.go_to(iter);
+                        None
+                    }
                     _ => {
                         self.status = Status::Error(format!("Invalid command: {cmd}"));
                         None
@@ -235,38 +329,52 @@ impl App<'_> {
     fn parse_search(&mut self, command: &[&str]) -> Option<SearchType> {
         let mut command_iter = command.iter();
         let mut file = FileFilterType::None;
-        let mut filter = Filter::None;
+        let mut filters = FilterBuilder::default();
+        let mut options = SearchOptions::default();
 
         // TODO: allow searching with specific langauges
         let name = unwrap_set_error!(self, command_iter.next(), "No function name");
         while let Some(cmd) = command_iter.next() {
             match *cmd {
                 "date" => {
-                    filter = match *unwrap_set_error!(self, command_iter.next(), "No date given") {
-                        "range" => Filter::DateRange(
-                            unwrap_set_error!(self, command_iter.next(), "No start date given")
-                                .to_string(),
-                            unwrap_set_error!(self, command_iter.next(), "No end date given")
-                                .to_string(),
-                        ),
-                        date => Filter::Date(date.to_string()),
-                    };
+                    filters.push(self.parse_date(&mut command_iter)?);
                 }
                 "commit" => {
-                    filter = Filter::CommitHash(
+                    filters.push(Filter::CommitHash(
                         unwrap_set_error!(self, command_iter.next(), "No commit given").to_string(),
+                    ));
+                }
+                "committer" => {
+                    filters.push(
+                        match *unwrap_set_error!(
+                            self,
+                            command_iter.next(),
+                            "No committer name given"
+                        ) {
+                            "email" => Filter::CommitterEmail(
+                                unwrap_set_error!(self, command_iter.next(), "No email given")
+                                    .to_string(),
+                            ),
+                            name => Filter::Committer(name.to_string()),
+                        },
                     );
                 }
                 "author" => {
-                    filter =
+                    filters.push(
                         match *unwrap_set_error!(self, command_iter.next(), "No author name given")
                         {
                             "email" => Filter::AuthorEmail(
                                 unwrap_set_error!(self, command_iter.next(), "No email given")
                                     .to_string(),
                             ),
+                            "regex" => Filter::AuthorRegex(
+                                unwrap_set_error!(self, command_iter.next(), "No regex given")
+                                    .to_string(),
+                            ),
+                            "date" => self.parse_author_date(&mut command_iter)?,
                             name => Filter::Author(name.to_string()),
-                        };
+                        },
+                    );
                 }
                 "file" => {
                     file = match *unwrap_set_error!(self, command_iter.next(), "Invalid file type")
@@ -274,6 +382,8 @@ impl App<'_> {
                         "absolute" => FileFilterType::Absolute,
                         "relative" => FileFilterType::Relative,
                         "directory" => FileFilterType::Directory,
+                        "glob" => FileFilterType::Glob,
+                        "regex" => FileFilterType::Regex,
                         _ => {
                             self.status = Status::Error("Invalid file type".to_string());
                             return None;
@@ -283,10 +393,62 @@ impl App<'_> {
                     );
                 }
                 "message" => {
-                    filter = Filter::Message(
+                    filters.push(Filter::Message(
                         unwrap_set_error!(self, command_iter.next(), "No commit message given")
                             .to_string(),
-                    )
+                    ));
+                }
+                "follow" => {
+                    options.follow = true;
+                }
+                "renames" => {
+                    options.follow_function_renames = true;
+                }
+                "cache" => {
+                    options.disk_cache = true;
+                }
+                "first-parent" => {
+                    options.merges = MergeMode::FirstParent;
+                }
+                "no-merges" => {
+                    options.merges = MergeMode::SkipMerges;
+                }
+                "merges" => {
+                    options.merges = MergeMode::MergesOnly;
+                }
+                "exclude" => {
+                    options.exclude.push(
+                        unwrap_set_error!(self, command_iter.next(), "No exclude pattern given")
+                            .to_string(),
+                    );
+                }
+                "pickaxe" => {
+                    options.pickaxe = Some(
+                        match *unwrap_set_error!(
+                            self,
+                            command_iter.next(),
+                            "No pickaxe string given"
+                        ) {
+                            "regex" => Pickaxe::Regex(
+                                unwrap_set_error!(self, command_iter.next(), "No regex given")
+                                    .to_string(),
+                            ),
+                            string => Pickaxe::Literal(string.to_string()),
+                        },
+                    );
+                }
+                "revision" => {
+                    options.revision = Some(
+                        unwrap_set_error!(self, command_iter.next(), "No revision given")
+                            .to_string(),
+                    );
+                }
+                "not" => filters.not(),
+                "or" => {
+                    if let Err(e) = filters.or() {
+                        self.status = Status::Error(e.to_string());
+                        return None;
+                    }
                 }
                 _ => {
                     self.status = Status::Error(format!("Invalid search command: {cmd}"));
@@ -294,79 +456,112 @@ impl App<'_> {
                 }
             }
         }
+        let filter = match filters.build() {
+            Ok(filter) => filter,
+            Err(e) => {
+                self.status = Status::Error(e.to_string());
+                return None;
+            }
+        };
+        options.cancel = Some(self.new_search());
         Some(SearchType {
             search: name.to_string(),
             file,
             filter,
+            options,
         })
     }
 
     fn parse_filter(&mut self, command: &[&str]) -> Option<Filter> {
         let mut command_iter = command.iter();
-        let mut filter = Filter::None;
+        let mut filters = FilterBuilder::default();
         while let Some(cmd) = command_iter.next() {
             match cmd {
                 &"language" => {
                     // TODO: support specifying many languages
-                    filter = Filter::Language(
+                    filters.push(Filter::Language(
                         unwrap_set_error!(self, command_iter.next(), "No language given")
                             .to_string(),
-                    );
+                    ));
                 }
                 &"date" => {
-                    filter = match *unwrap_set_error!(self, command_iter.next(), "No date given") {
-                        "range" => Filter::DateRange(
-                            unwrap_set_error!(self, command_iter.next(), "No start date given")
-                                .to_string(),
-                            unwrap_set_error!(self, command_iter.next(), "No end date given")
-                                .to_string(),
-                        ),
-                        date => Filter::Date(date.to_string()),
-                    };
+                    filters.push(self.parse_date(&mut command_iter)?);
                 }
                 &"commit" => {
-                    filter = Filter::CommitHash(
+                    filters.push(Filter::CommitHash(
                         unwrap_set_error!(self, command_iter.next(), "No commit given").to_string(),
+                    ));
+                }
+                &"committer" => {
+                    filters.push(
+                        match *unwrap_set_error!(
+                            self,
+                            command_iter.next(),
+                            "No committer name given"
+                        ) {
+                            "email" => Filter::CommitterEmail(
+                                unwrap_set_error!(self, command_iter.next(), "No email given")
+                                    .to_string(),
+                            ),
+                            name => Filter::Committer(name.to_string()),
+                        },
                     );
                 }
                 &"author" => {
-                    filter =
+                    filters.push(
                         match *unwrap_set_error!(self, command_iter.next(), "No author name given")
                         {
                             "email" => Filter::AuthorEmail(
                                 unwrap_set_error!(self, command_iter.next(), "No email given")
                                     .to_string(),
                             ),
+                            "regex" => Filter::AuthorRegex(
+                                unwrap_set_error!(self, command_iter.next(), "No regex given")
+                                    .to_string(),
+                            ),
+                            "date" => self.parse_author_date(&mut command_iter)?,
                             name => Filter::Author(name.to_string()),
-                        };
+                        },
+                    );
                 }
                 &"file" => {
-                    filter =
-                        match *unwrap_set_error!(self, command_iter.next(), "Invalid file type") {
-                            "absolute" => Filter::FileAbsolute,
-                            "relative" => Filter::FileRelative,
-                            "directory" => Filter::Directory,
-                            _ => {
-                                self.status = Status::Error("Invalid file type".to_string());
-                                return None;
-                            }
-                        }(
-                            unwrap_set_error!(self, command_iter.next(), "No file given")
-                                .to_string(),
-                        );
+                    filters.push(match *unwrap_set_error!(
+                        self,
+                        command_iter.next(),
+                        "Invalid file type"
+                    ) {
+                        "absolute" => Filter::FileAbsolute,
+                        "relative" => Filter::FileRelative,
+                        "directory" => Filter::Directory,
+                        "glob" => Filter::FileGlob,
+                        "regex" => Filter::FileRegex,
+                        _ => {
+                            self.status = Status::Error("Invalid file type".to_string());
+                            return None;
+                        }
+                    }(
+                        unwrap_set_error!(self, command_iter.next(), "No file given").to_string(),
+                    ));
+                }
+                &"not" => filters.not(),
+                &"or" => {
+                    if let Err(e) = filters.or() {
+                        self.status = Status::Error(e.to_string());
+                        return None;
+                    }
                 }
                 &"message" => {
-                    filter = Filter::Message(
+                    filters.push(Filter::Message(
                         unwrap_set_error!(self, command_iter.next(), "No commit message given")
                             .to_string(),
-                    )
+                    ));
                 }
                 filter_name => {
-                    if let Some(filters) =
+                    if let Some(pl_filters) =
                         function_grep::filter::Filters::default().get_filter(filter_name)
                     {
                         let rest = command_iter.copied().collect::<Vec<_>>().join(" ");
-                        let filt = match filters.to_filter(&rest) {
+                        let filt = match pl_filters.to_filter(&rest) {
                             Ok(val) => val,
                             Err(e) => {
                                 self.status = Status::Error(
@@ -380,7 +575,7 @@ impl App<'_> {
                             }
                         };
                         log::info!("filtering by {:?}", filt);
-                        filter = Filter::PLFilter(filt);
+                        filters.push(Filter::PLFilter(filt));
                         break;
                     } else {
                         self.status =
@@ -390,18 +585,116 @@ impl App<'_> {
                 }
             }
         }
-        Some(filter)
+        match filters.build() {
+            Ok(filter) => Some(filter),
+            Err(e) => {
+                self.status = Status::Error(e.to_string());
+                None
+            }
+        }
+    }
+
+    // parses what comes after `date`: a date, `range <start> <end>`, `since <date>` or `until <date>`
+    fn parse_date(&mut self, command_iter: &mut std::slice::Iter<'_, &str>) -> Option<Filter> {
+        Some(
+            match *unwrap_set_error!(self, command_iter.next(), "No date given") {
+                "local" => self.parse_date(command_iter)?.local_time(),
+                "range" => Filter::DateRange(
+                    unwrap_set_error!(self, command_iter.next(), "No start date given").to_string(),
+                    unwrap_set_error!(self, command_iter.next(), "No end date given").to_string(),
+                ),
+                "since" => Filter::since(*unwrap_set_error!(
+                    self,
+                    command_iter.next(),
+                    "No start date given"
+                )),
+                "until" => Filter::until(*unwrap_set_error!(
+                    self,
+                    command_iter.next(),
+                    "No end date given"
+                )),
+                date => Filter::Date(date.to_string()),
+            },
+        )
+    }
+
+    /// parses the dates after `author date`, which can only be a range as there is no closest date for when commits were authored
+    fn parse_author_date(
+        &mut self,
+        command_iter: &mut std::slice::Iter<'_, &str>,
+    ) -> Option<Filter> {
+        fn author_dates(filter: Filter) -> Option<Filter> {
+            match filter {
+                Filter::DateRange(start, end) => Some(Filter::AuthorDateRange(start, end)),
+                Filter::LocalTime(filter) => author_dates(*filter).map(Filter::local_time),
+                _ => None,
+            }
+        }
+        let filter = author_dates(self.parse_date(command_iter)?);
+        if filter.is_none() {
+            self.status =
+                Status::Error("Author dates have to be a range, since or until".to_string());
+        }
+        filter
+    }
+
+    /// moves to the commit after `goto` in the current history: `first`, `last`, `date <date>` or (`commit`) a commit hash prefix
+    fn go_to(&mut self, command: &[&str]) {
+        let CommandResult::History(history) = &mut self.cmd_output else {
+            self.status = Status::Error("No history to move through".to_string());
+            return;
+        };
+        let moved = match command {
+            ["first"] => history
+                .go_to_first()
+                .ok_or(git_function_history::Error::NoHistory),
+            ["last"] => history
+                .go_to_last()
+                .ok_or(git_function_history::Error::NoHistory),
+            ["date", date] => history.go_to_date(date),
+            ["commit", hash] | [hash] => history.go_to_commit(hash),
+            _ => {
+                self.status = Status::Error("Invalid goto command".to_string());
+                return;
+            }
+        };
+        self.status = match moved {
+            Ok(()) => Status::Ok(history.get_commit().map(|commit| {
+                format!(
+                    "moved to commit {} ({} of {})",
+                    commit.commit_hash(),
+                    history.current_index() + 1,
+                    history.len()
+                )
+            })),
+            Err(e) => Status::Error(e.to_string()),
+        };
     }
 
-    fn parse_list(&mut self, command: &[&str]) -> Option<ListType> {
-        match command {
-            ["dates"] => Some(ListType::Dates),
-            ["commits"] => Some(ListType::Commits),
+    fn parse_list(&mut self, command: &[&str]) -> Option<(ListType, SearchOptions)> {
+        let (list_type, revision) = match command {
+            ["dates", revision @ ..] => (ListType::Dates, revision),
+            ["commits", revision @ ..] => (ListType::Commits, revision),
             _ => {
                 self.status = Status::Error("Invalid list type".to_string());
-                None
+                return None;
             }
-        }
+        };
+        let revision = match revision {
+            [] => None,
+            ["revision", revision] => Some((*revision).to_string()),
+            _ => {
+                self.status = Status::Error("Invalid list revision".to_string());
+                return None;
+            }
+        };
+        Some((
+            list_type,
+            SearchOptions {
+                revision,
+                ..SearchOptions::default()
+            },
+        ))
     }
 
     pub fn get_result(&mut self) {
@@ -417,11 +710,29 @@ impl App<'_> {
                     log::warn!("{}", e);
                     self.status = Status::Error(e);
                 }
+                // more of what a search found, the history stays at the commit that is shown
+                (CommandResult::MoreCommits(commits), status) => {
+                    log::trace!("got more results of last command");
+                    if let CommandResult::History(history) = &mut self.cmd_output {
+                        history.extend(commits);
+                    }
+                    if let Status::Ok(msg) = status {
+                        self.status = Status::Ok(msg);
+                    }
+                }
                 (t, Status::Ok(msg)) => {
                     log::info!("got results of last command");
                     self.status = Status::Ok(msg);
                     self.cmd_output = t;
                 }
+                (_, Status::Progress(progress)) => {
+                    self.status = Status::Progress(progress);
+                }
+                // what a search found so far, while it is still running
+                (t @ CommandResult::History(_), Status::Loading) => {
+                    log::trace!("got partial results of last command");
+                    self.cmd_output = t;
+                }
                 _ => {}
             },
             Err(e) => match e {
@@ -1,11 +1,12 @@
 use std::collections::BTreeMap;
 
 use function_history_backend_thread::types::Status;
+use git_function_history::Progress;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation},
+    widgets::{Block, Borders, LineGauge, Paragraph, Scrollbar, ScrollbarOrientation},
     Frame,
 };
 
@@ -57,11 +58,11 @@ pub fn draw(rect: &mut Frame, app: &mut App) {
         &app.input_buffer,
         *body_chunks.get(1).expect("could not get area to draw"),
     );
-    let status = draw_status(app.status());
-    rect.render_widget(
-        status,
-        *body_chunks.get(2).expect("could not get area to draw"),
-    );
+    let status_area = *body_chunks.get(2).expect("could not get area to draw");
+    match app.status() {
+        Status::Progress(progress) => rect.render_widget(draw_progress(progress), status_area),
+        status => rect.render_widget(draw_status(status), status_area),
+    }
 }
 
 fn draw_body(app: &mut App, mut pos: Rect, frame: &mut Frame) {
@@ -83,7 +84,7 @@ fn draw_body(app: &mut App, mut pos: Rect, frame: &mut Frame) {
     };
     let tick_text: Vec<Line> = match &app.cmd_output {
         CommandResult::None => match app.status {
-            Status::Loading => vec![Line::from(format!(
+            Status::Loading | Status::Progress(_) => vec![Line::from(format!(
                 "Loading{}",
                 ".".repeat(
                     ((std::time::SystemTime::now()
@@ -152,3 +153,16 @@ fn draw_status<'a>(status: &Status) -> Paragraph<'a> {
                 .style(Style::default().fg(Color::White)),
         )
 }
+
+fn draw_progress<'a>(progress: &Progress) -> LineGauge<'a> {
+    LineGauge::default()
+        .label(format!("Searching... {progress}"))
+        .ratio(f64::from(progress.fraction().unwrap_or(0.0)).clamp(0.0, 1.0))
+        .filled_style(Style::default().fg(Color::LightCyan))
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .borders(Borders::BOTTOM)
+                .style(Style::default().fg(Color::White)),
+        )
+}
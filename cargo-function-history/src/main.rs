@@ -1,8 +1,8 @@
 use std::{cell::RefCell, env, error::Error, process::exit, rc::Rc, sync::mpsc};
 
 use cargo_function_history::{app::App, start_ui};
-use function_history_backend_thread::types::{FullCommand, SearchType, Status};
-use git_function_history::{FileFilterType, Filter};
+use function_history_backend_thread::types::{SearchType, Status};
+use git_function_history::{FileFilterType, Filter, MergeMode, Pickaxe, SearchOptions};
 use log::info;
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -13,21 +13,17 @@ fn main() -> Result<(), Box<dyn Error>> {
     info!("Starting cargo function history");
     let (tx_t, rx_m) = mpsc::channel();
     let (tx_m, rx_t) = mpsc::channel();
-    function_history_backend_thread::command_thread(rx_t, tx_t, true);
-    info!("started command thread");
     let config = parse_args();
-    let status = match config.function_name {
-        string if string.is_empty() => Status::Ok(None),
-        string => {
-            tx_m.send(FullCommand::Search(SearchType::new(
-                string,
-                config.file_type,
-                config.filter,
-            )))?;
-            Status::Loading
-        }
-    };
-    let app = Rc::new(RefCell::new(App::new((tx_m, rx_m), status)));
+    function_history_backend_thread::command_thread(rx_t, tx_t, true, config.options.repo.clone());
+    info!("started command thread");
+    let mut app = App::new((tx_m, rx_m), Status::Ok(None));
+    if !config.function_name.is_empty() {
+        app.search(
+            SearchType::new(config.function_name, config.file_type, config.filter)
+                .with_options(config.options),
+        );
+    }
+    let app = Rc::new(RefCell::new(app));
     start_ui(app)?;
     Ok(())
 }
@@ -38,9 +34,25 @@ fn usage() -> ! {
     println!("  --help - show this message");
     println!("  --file-absolute - search the exact file with the filename specified after the function name");
     println!("  --file-relative - search any file ending with the filename specified after the function name");
+    println!("  --file-glob - search any file matching the glob specified after the function name (like src/**/handlers/*.rs)");
+    println!("  --file-regex - search any file whose path matches the regex specified after the function name");
+    println!("  --exclude=<pattern> - do not search files or directories matching the glob (like vendor or src/generated), can be given more than once");
+    println!("  --follow - keep following the file (specified with --file-absolute) across renames and moves");
+    println!("  --follow-renames - keep following the function when it gets renamed (by looking for a function with a similar body)");
+    println!("  --cache - keep where the function was found in each file (in .git/function-history-cache), so the next search of the function does not parse the files that did not change again");
+    println!("  --first-parent - only follow the first parent of merge commits");
+    println!("  --no-merges - do not search merge commits");
+    println!("  --merges - only search merge commits");
+    println!("  --pickaxe=<string> - only show the commits that changed how many times the string is in the function (like git log -S)");
+    println!("  --pickaxe-regex=<regex> - only show the commits that changed how many times the regex matches in the function (like git log -G)");
+    println!("  --repo=<path> - search the git repository at the given path instead of the one in the current directory");
+    println!("  --revision=<revision> - search the history of the given branch, tag, commit or range (like v1.0..main) instead of HEAD");
     println!("  --filter-date=<date> - filter to the given date");
+    println!("      Dates can be in rfc2822 or ISO 8601 format (like 2024-01-31 or 2024-01-31T12:00:00+02:00), or relative (like yesterday or 2.weeks.ago)");
     println!("  --filter-commit-hash=<hash> - filter to the given commit hash");
-    println!("  --filter-date-range=<date1>:<date2> - filter to the given date range");
+    println!("  --filter-date-range=<date1>..<date2> - filter to the given date range, leave out either date to not limit that side of the range");
+    println!("  --since=<date> - filter to the commits since the given date");
+    println!("  --until=<date> - filter to the commits until the given date (the whole day for a date without a time)");
     println!("  --lang=[lang] - filter to the given language");
     println!("      Available languages: rust, python, ruby, go, umpl, all");
     println!("      Default: all");
@@ -52,6 +64,7 @@ struct Config {
     function_name: String,
     filter: Filter,
     file_type: FileFilterType,
+    options: SearchOptions,
     // language: git_function_history::languages::Language,
 }
 
@@ -60,6 +73,7 @@ fn parse_args() -> Config {
         function_name: String::new(),
         filter: Filter::None,
         file_type: FileFilterType::None,
+        options: SearchOptions::default(),
         // language: git_function_history::languages::Language::All,
     };
     env::args().enumerate().skip(1).for_each(|arg| {
@@ -106,6 +120,74 @@ fn parse_args() -> Config {
                         _ => {}
                     }
                 }
+                "--file-glob" | "--file-regex" => {
+                    let path = match &config.file_type {
+                        FileFilterType::Relative(path) | FileFilterType::Absolute(path) => path.to_string(),
+                        _ => {
+                            eprintln!("Error no file name specified");
+                            exit(1);
+                        }
+                    };
+                    config.file_type = if arg.1 == "--file-glob" {
+                        FileFilterType::Glob(path)
+                    } else {
+                        FileFilterType::Regex(path)
+                    };
+                }
+                "--follow" => {
+                    config.options.follow = true;
+                }
+                "--follow-renames" => {
+                    config.options.follow_function_renames = true;
+                }
+                "--cache" => {
+                    config.options.disk_cache = true;
+                }
+                "--first-parent" => {
+                    config.options.merges = MergeMode::FirstParent;
+                }
+                "--no-merges" => {
+                    config.options.merges = MergeMode::SkipMerges;
+                }
+                "--merges" => {
+                    config.options.merges = MergeMode::MergesOnly;
+                }
+                string if string.starts_with("--repo=") => {
+                    let repo = match string.split_once('=') {
+                        Some((_, repo)) if !repo.is_empty() => repo,
+                        _ => {
+                            eprintln!("Error no repository path specified");
+                            exit(1);
+                        }
+                    };
+                    config.options.repo = Some(repo.into());
+                }
+                string
+                    if string.starts_with("--pickaxe=") || string.starts_with("--pickaxe-regex=") =>
+                {
+                    let (flag, pickaxe) = match string.split_once('=') {
+                        Some((flag, pickaxe)) if !pickaxe.is_empty() => (flag, pickaxe.to_string()),
+                        _ => {
+                            eprintln!("Error no pickaxe string specified");
+                            exit(1);
+                        }
+                    };
+                    config.options.pickaxe = Some(if flag == "--pickaxe" {
+                        Pickaxe::Literal(pickaxe)
+                    } else {
+                        Pickaxe::Regex(pickaxe)
+                    });
+                }
+                string if string.starts_with("--revision=") => {
+                    let revision = match string.split_once('=') {
+                        Some((_, revision)) if !revision.is_empty() => revision,
+                        _ => {
+                            eprintln!("Error no revision specified");
+                            exit(1);
+                        }
+                    };
+                    config.options.revision = Some(revision.to_string());
+                }
                 string if string.starts_with("--filter-date=") => {
                     let date = match string.split('=').nth(1) {
                         Some(string) => string,
@@ -126,7 +208,10 @@ fn parse_args() -> Config {
                     };
                     config.filter = Filter::CommitHash(hash.to_string());
                 }
-                string if string.starts_with("--date-range=") => {
+                string
+                    if string.starts_with("--filter-date-range=")
+                        || string.starts_with("--date-range=") =>
+                {
                     let date_range = match string.split('=').nth(1) {
                         Some(string) => string,
                         None => {
@@ -134,7 +219,11 @@ fn parse_args() -> Config {
                             exit(1);
                         }
                     };
-                    let date_range = match date_range.split_once(':') {
+                    // dates can have colons in them, so those are only used to separate the dates if there is no `..`
+                    let date_range = match date_range
+                        .split_once("..")
+                        .or_else(|| date_range.split_once(':'))
+                    {
                         Some(string_tuple) => string_tuple,
                         None => {
                             eprintln!("Error no end date specified");
@@ -143,6 +232,30 @@ fn parse_args() -> Config {
                     };
                     config.filter = Filter::DateRange(date_range.0.to_string(), date_range.1.to_string());
                 }
+                string if string.starts_with("--since=") || string.starts_with("--until=") => {
+                    let (flag, date) = match string.split_once('=') {
+                        Some((flag, date)) if !date.is_empty() => (flag, date),
+                        _ => {
+                            eprintln!("Error no date specified");
+                            exit(1);
+                        }
+                    };
+                    config.filter = if flag == "--since" {
+                        Filter::since(date)
+                    } else {
+                        Filter::until(date)
+                    };
+                }
+                string if string.starts_with("--exclude=") => {
+                    let pattern = match string.split_once('=') {
+                        Some((_, pattern)) if !pattern.is_empty() => pattern,
+                        _ => {
+                            eprintln!("Error no exclude pattern specified");
+                            exit(1);
+                        }
+                    };
+                    config.options.exclude.push(pattern.to_string());
+                }
                 // TODO: search by language.
                 _ => {
                     println!("Error:\n\tUnknown argument: {}\n\tTip: use --help to see available arguments.", arg.1);
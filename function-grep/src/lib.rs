@@ -7,8 +7,10 @@
 use core::fmt;
 
 use filter::{InstantiatedFilter, InstantiatedFilterType};
+use std::sync::{Arc, OnceLock};
 use supported_languages::InstantiatedLanguage;
-use tree_sitter::{LanguageError, QueryError, Range, Tree};
+use tree_sitter::{Language, LanguageError, Node, QueryError, Tree};
+pub use tree_sitter::{Point, Range};
 #[allow(missing_debug_implementations)]
 #[derive(Hash, PartialEq, Eq, Debug, Clone)]
 pub enum SupportedLanguages {
@@ -59,8 +61,29 @@ pub enum Error {
     NoResultsForSearch,
     /// If the filter is not valid for this langauge
     FilterLangaugeMismatch,
+    /// If the text of a compact file (see [`ParsedFile::compact`]) could not be loaded again.
+    FileUnavailable(String),
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FileTypeUnkown(file) => write!(f, "no supported language for file {file}"),
+            Self::ParseError(file) => write!(f, "could not parse {file}"),
+            Self::GrammarLoad(language, e) => {
+                write!(f, "could not load grammar for {language}: {e}")
+            }
+            Self::InvalidQuery(language, e) => write!(f, "invalid query for {language}: {e}"),
+            Self::NoSuchResultsForFilter => write!(f, "no results left after filtering"),
+            Self::NoResultsForSearch => write!(f, "no results found"),
+            Self::FilterLangaugeMismatch => write!(f, "filter is not valid for this language"),
+            Self::FileUnavailable(file) => write!(f, "could not load the text of {file}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 /// Tries to find the appropiate language for the given file extension [`ext`] based on the list of
 /// languages [`langs`] provided.
 ///
@@ -96,21 +119,94 @@ pub fn get_file_type_from_file<'a>(
         .and_then(|ext| get_file_type_from_file_ext(ext, langs))
 }
 
-#[derive(Debug, Clone)]
+/// Loads the text of a compact file again, see [`ParsedFile::compact`].
+pub type LoadFile = Arc<dyn Fn() -> Option<Arc<str>> + Send + Sync>;
+
+#[derive(Clone)]
 /// The result of finding function with a given name.
 /// Use [`Self::search_file`] or [`Self::search_file_with_name`] to do the searching.
 pub struct ParsedFile {
     // I believe we cannot store something refernceing the tree, so we cannot directly store the
     // results of the query, but just their ranges so in the [`filter`] method we use the tree to
     // obtain the correct nodes from their ranges
-    file: Box<str>,
+    //
+    // the text and tree of the whole file are shared between the clones of this file, so a compact
+    // file only gets loaded again once
+    whole: Arc<WholeFile>,
+    // the lines of each found function (in the same order as the results), so a compact file can
+    // still show its functions
+    functions: Arc<[FunctionLines]>,
     file_name: Option<Box<str>>,
     function_name: Box<str>,
     // TODO: maybe each supported language could define filters
     // if so we would store InstantiatedLanguage here
     language_type: Box<str>,
-    tree: Tree,
+    language: Language,
     results: Box<[Range]>,
+    // the tree sitter node kind of each result, kept so a compact file does not have to be parsed
+    // again to get them
+    kinds: Arc<[&'static str]>,
+}
+
+/// The text and tree of a whole file, which are only loaded when they are needed.
+#[derive(Default)]
+struct WholeFile {
+    // only empty for compact files (until it gets loaded again)
+    text: OnceLock<Arc<str>>,
+    load: Option<LoadFile>,
+    // when the results are already known (see [`ParsedFile::from_results`]) or the file is
+    // compact we only parse the file if the tree is actually needed
+    tree: OnceLock<Tree>,
+}
+
+impl WholeFile {
+    fn new(text: &str, tree: Option<Tree>) -> Self {
+        Self {
+            text: OnceLock::from(Arc::from(text)),
+            load: None,
+            tree: tree.map_or_else(OnceLock::new, OnceLock::from),
+        }
+    }
+}
+
+/// The (whole) lines a function is on, and where the function starts in them.
+struct FunctionLines {
+    lines: Box<str>,
+    start: usize,
+}
+
+impl FunctionLines {
+    fn find(file: &str, ranges: &[Range]) -> Arc<[Self]> {
+        ranges
+            .iter()
+            .map(|range| {
+                let start = file[..range.start_byte].rfind('\n').map_or(0, |i| i + 1);
+                let end = file[range.end_byte..]
+                    .find('\n')
+                    .map_or(file.len(), |i| range.end_byte + i);
+                Self {
+                    lines: file[start..end].into(),
+                    start: range.start_byte - start,
+                }
+            })
+            .collect()
+    }
+
+    fn function(&self, range: &Range) -> &str {
+        &self.lines[self.start..self.start + range.end_byte - range.start_byte]
+    }
+}
+
+impl fmt::Debug for ParsedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParsedFile")
+            .field("file_name", &self.file_name)
+            .field("function_name", &self.function_name)
+            .field("language", &self.language_type)
+            .field("results", &self.results)
+            .field("compact", &self.whole.load.is_some())
+            .finish_non_exhaustive()
+    }
 }
 
 impl ParsedFile {
@@ -123,15 +219,107 @@ impl ParsedFile {
         results: Box<[Range]>,
     ) -> Self {
         Self {
-            file: file.into(),
+            functions: FunctionLines::find(file, &results),
             function_name: function_name.into(),
             language_type: language_type.into(),
-            tree,
+            language: (*tree.language()).clone(),
+            kinds: node_kinds(tree.root_node(), &results),
+            whole: Arc::new(WholeFile::new(file, Some(tree))),
             results,
             file_name: None,
         }
     }
 
+    #[must_use]
+    /// Makes a [`ParsedFile`] from already known results (for example ones that were cached from
+    /// a previous search) without parsing the file.
+    /// The file only gets parsed when it is needed, like when filtering.
+    ///
+    /// The [`kinds`] are the tree sitter node kinds of the results (see [`Self::result_kinds`]).
+    ///
+    /// Returns `None` if the results do not fit in the file (they end after the end of the file or
+    /// do not start and end on character boundaries), or there is not one kind for each result,
+    /// then the file should be searched again.
+    pub fn from_results(
+        code: &str,
+        file_name: &str,
+        language: &InstantiatedLanguage<'_>,
+        results: Box<[Range]>,
+        kinds: Box<[&'static str]>,
+    ) -> Option<Self> {
+        let fits = |range: &Range| {
+            range.start_byte <= range.end_byte
+                && range.end_byte <= code.len()
+                && code.is_char_boundary(range.start_byte)
+                && code.is_char_boundary(range.end_byte)
+        };
+        if kinds.len() != results.len() || !results.iter().all(fits) {
+            return None;
+        }
+        Some(
+            Self {
+                functions: FunctionLines::find(code, &results),
+                function_name: language.search_name().into(),
+                language_type: language.name().into(),
+                language: language.language().clone(),
+                whole: Arc::new(WholeFile::new(code, None)),
+                results,
+                kinds: kinds.into(),
+                file_name: None,
+            }
+            .set_file_name(file_name),
+        )
+    }
+
+    #[must_use]
+    /// Makes this file compact: only the lines of the found functions are kept, the text and tree
+    /// of the whole file are dropped, and the text is loaded again with [`load`] when it is needed
+    /// (like when filtering).
+    ///
+    /// Use this when keeping many files around, like the same file in many commits.
+    pub fn compact(mut self, load: LoadFile) -> Self {
+        self.whole = Arc::new(WholeFile {
+            load: Some(load),
+            ..WholeFile::default()
+        });
+        self
+    }
+
+    /// Get the text of the whole file, a compact file (see [`Self::compact`]) loads it again.
+    ///
+    /// # Errors
+    /// If the text of a compact file could not be loaded.
+    pub fn file_text(&self) -> Result<&str, Error> {
+        if let Some(text) = self.whole.text.get() {
+            return Ok(text);
+        }
+        let text = self
+            .whole
+            .load
+            .as_ref()
+            .and_then(|load| load())
+            .ok_or_else(|| {
+                Error::FileUnavailable(self.file_name().unwrap_or("file").to_string())
+            })?;
+        Ok(self.whole.text.get_or_init(|| text))
+    }
+
+    fn tree(&self) -> Result<&Tree, Error> {
+        if let Some(tree) = self.whole.tree.get() {
+            return Ok(tree);
+        }
+        let file = self.file_text()?;
+        // the grammar was already loaded once to find the results, so we just treat it failing to
+        // load now as the file failing to parse
+        let mut parser = tree_sitter::Parser::new();
+        let tree = parser
+            .set_language(&self.language)
+            .ok()
+            .and_then(|()| parser.parse(file, None))
+            .ok_or_else(|| Error::ParseError(file.to_string()))?;
+        Ok(self.whole.tree.get_or_init(|| tree))
+    }
+
     // TODO: maybe only make this hidden and expose a filter method that takes in some sort of
     // filter trait
     //
@@ -155,17 +343,20 @@ impl ParsedFile {
     }
 
     fn filter_inner<T>(&self, f: &InstantiatedFilter<T>) -> Result<Self, Error> {
-        let root = self.tree.root_node();
+        let file = self.file_text()?;
+        let root = self.tree()?.root_node();
         let ranges: Box<[Range]> = self
             .ranges()
             .filter_map(|range| root.descendant_for_point_range(range.start_point, range.end_point))
-            .filter(|n| f.filter(n, &self.file))
+            .filter(|n| f.filter(n, file))
             .map(|n| n.range())
             .collect();
         if ranges.is_empty() {
             return Err(Error::NoSuchResultsForFilter);
         }
         let clone = Self {
+            functions: FunctionLines::find(file, &ranges),
+            kinds: node_kinds(root, &ranges),
             results: ranges,
             ..self.clone()
         };
@@ -200,14 +391,7 @@ impl ParsedFile {
     /// If no results are found for this function name.
     pub fn search_file(code: &str, language: &InstantiatedLanguage<'_>) -> Result<Self, Error> {
         let code_bytes = code.as_bytes();
-        let mut parser = tree_sitter::Parser::new();
-        let ts_lang = language.language();
-        parser
-            .set_language(ts_lang)
-            .map_err(|lang_err| Error::GrammarLoad(language.name(), lang_err))?;
-        let parsed = parser
-            .parse(code, None)
-            .ok_or_else(|| Error::ParseError(code.to_string()))?;
+        let parsed = parse(code, language)?;
 
         let node = parsed.root_node();
         let command_ranges = language.run_query(node, code_bytes);
@@ -224,6 +408,42 @@ impl ParsedFile {
         ))
     }
 
+    /// Same as [`Self::search_file`], but searches for more than one function name at once, so the
+    /// code only has to be parsed once.
+    /// Each of the [`languages`] should be the same language, instantiated for a different name.
+    ///
+    /// Returns what was found for each language (in the same order), or `None` if nothing was
+    /// found for its name.
+    ///
+    /// # Errors
+    /// If something with tree sitter goes wrong.
+    /// If the code cannot be parsed properly.
+    pub fn search_file_many(
+        code: &str,
+        languages: &[&InstantiatedLanguage<'_>],
+    ) -> Result<Vec<Option<Self>>, Error> {
+        let Some(first) = languages.first() else {
+            return Ok(vec![]);
+        };
+        let parsed = parse(code, first)?;
+        let node = parsed.root_node();
+        Ok(languages
+            .iter()
+            .map(|language| {
+                let command_ranges = language.run_query(node, code.as_bytes());
+                (!command_ranges.is_empty()).then(|| {
+                    Self::new(
+                        code,
+                        language.search_name(),
+                        language.name(),
+                        parsed.clone(),
+                        command_ranges,
+                    )
+                })
+            })
+            .collect())
+    }
+
     /// Search for all function with the name [`name`], in string [`code`] with a language found
     /// from the file name [`file_name`] and the languages [`langs`].
     ///
@@ -242,7 +462,10 @@ impl ParsedFile {
             .map(|file| file.set_file_name(file_name))
     }
 
-    fn set_file_name(mut self, file_name: &str) -> Self {
+    #[must_use]
+    /// Set the file name of this file.
+    /// Useful when the same file contents are in more than one file.
+    pub fn set_file_name(mut self, file_name: &str) -> Self {
         self.file_name.replace(file_name.into());
         self
     }
@@ -258,6 +481,99 @@ impl ParsedFile {
     pub const fn results(&self) -> &[Range] {
         &self.results
     }
+
+    /// Get the text of each found function.
+    /// The texts are in the same order as [`Self::results`].
+    pub fn result_texts(&self) -> impl Iterator<Item = &str> {
+        self.functions
+            .iter()
+            .zip(self.ranges())
+            .map(|(function, range)| function.function(range))
+    }
+
+    #[must_use]
+    /// Get the tree sitter node kind (like `function_item`) of each found function.
+    /// The kinds are in the same order as [`Self::results`], with an empty kind for a function
+    /// whose node could not be found again.
+    pub fn result_kinds(&self) -> &[&'static str] {
+        &self.kinds
+    }
+}
+
+/// Finds the node kind of each of the [`ranges`] in the tree under [`root`], with an empty kind
+/// for a range that has no node, so there is always one kind for each range.
+fn node_kinds(root: Node<'_>, ranges: &[Range]) -> Arc<[&'static str]> {
+    ranges
+        .iter()
+        .map(|range| {
+            root.descendant_for_point_range(range.start_point, range.end_point)
+                .map_or("", |node| node.kind())
+        })
+        .collect()
+}
+
+/// Parses [`code`] with the tree sitter grammar of [`language`].
+fn parse(code: &str, language: &InstantiatedLanguage<'_>) -> Result<Tree, Error> {
+    let mut parser = tree_sitter::Parser::new();
+    parser
+        .set_language(language.language())
+        .map_err(|lang_err| Error::GrammarLoad(language.name(), lang_err))?;
+    parser
+        .parse(code, None)
+        .ok_or_else(|| Error::ParseError(code.to_string()))
+}
+
+/// Finds every node in [`code`] that is one of the tree sitter node kinds [`kinds`] (like
+/// `function_item`) along with its name.
+///
+/// Unlike [`ParsedFile::search_file`] this does not look for a specific name, so it can be used to
+/// find functions that were renamed (use [`ParsedFile::result_kinds`] to get the kinds).
+/// Nodes that we cannot find a name for are skipped.
+///
+/// # Errors
+/// If something with tree sitter goes wrong.
+/// If the code cannot be parsed properly.
+pub fn find_named_nodes_of_kinds<'a>(
+    code: &'a str,
+    language: &InstantiatedLanguage<'_>,
+    kinds: &[&str],
+) -> Result<Vec<(Range, &'a str)>, Error> {
+    let parsed = parse(code, language)?;
+    let mut found = vec![];
+    let mut cursor = parsed.walk();
+    let mut stack = vec![parsed.root_node()];
+    while let Some(node) = stack.pop() {
+        if kinds.contains(&node.kind()) {
+            if let Some(name) =
+                node_name(node).and_then(|name| name.utf8_text(code.as_bytes()).ok())
+            {
+                found.push((node.range(), name));
+            }
+        }
+        stack.extend(node.named_children(&mut cursor));
+    }
+    found.sort_by_key(|(range, _)| range.start_byte);
+    Ok(found)
+}
+
+/// Most grammars have a name field for function like nodes, if not (like in C where the name is
+/// nested in the declarator) we use the first identifier we find.
+fn node_name(node: Node<'_>) -> Option<Node<'_>> {
+    node.child_by_field_name("name").or_else(|| {
+        let mut cursor = node.walk();
+        let mut stack = node.named_children(&mut cursor).collect::<Vec<_>>();
+        stack.reverse();
+        while let Some(node) = stack.pop() {
+            if node.kind().ends_with("identifier") {
+                return Some(node);
+            }
+            let mut cursor = node.walk();
+            let start = stack.len();
+            stack.extend(node.named_children(&mut cursor));
+            stack[start..].reverse();
+        }
+        None
+    })
 }
 
 impl IntoIterator for ParsedFile {
@@ -268,12 +584,8 @@ impl IntoIterator for ParsedFile {
     fn into_iter(self) -> Self::IntoIter {
         Box::new(
             self.ranges()
-                .map(|range| {
-                    (
-                        *range,
-                        self.file[range.start_byte..range.end_byte].to_string(),
-                    )
-                })
+                .copied()
+                .zip(self.result_texts().map(str::to_string))
                 .collect::<Vec<_>>()
                 .into_iter(),
         )
@@ -282,19 +594,16 @@ impl IntoIterator for ParsedFile {
 
 impl fmt::Display for ParsedFile {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let lines = self.file.lines().enumerate();
         let texts = self
-            .ranges()
-            .map(move |range| {
-                lines
-                    .clone()
-                    .filter_map(move |(line, str)| {
-                        if line >= range.start_point.row && line <= range.end_point.row {
-                            Some(format!("{}: {str}", line + 1))
-                        } else {
-                            None
-                        }
-                    })
+            .functions
+            .iter()
+            .zip(self.ranges())
+            .map(|(function, range)| {
+                function
+                    .lines
+                    .lines()
+                    .enumerate()
+                    .map(|(line, str)| format!("{}: {str}", range.start_point.row + line + 1))
                     .collect::<Vec<_>>()
                     .join("\n")
             })
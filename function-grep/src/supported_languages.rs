@@ -1,4 +1,9 @@
-use std::{ops::Deref, str, sync::atomic::AtomicUsize};
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Deref,
+    str,
+    sync::atomic::AtomicUsize,
+};
 use tree_sitter::{Language as TsLanguage, Node, Query, QueryError, Range};
 use tree_sitter_tags::{Tag, TagsConfiguration, TagsContext};
 // TODO: better api less boxing and more results
@@ -12,6 +17,7 @@ pub struct InstantiatedLanguage<'a> {
     search_name: &'a str,
     language: LanguageInformation,
     run_query: QueryFunction,
+    fingerprint: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -20,6 +26,18 @@ pub enum InstantiationError {
     Query(QueryError),
     Tags(tree_sitter_tags::Error),
 }
+
+impl std::fmt::Display for InstantiationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoMatchingField(field) => write!(f, "query has no capture named {field}"),
+            Self::Query(e) => write!(f, "invalid query: {e}"),
+            Self::Tags(e) => write!(f, "invalid tags query: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for InstantiationError {}
 pub trait HasLanguageInformation {
     /// The name of this language
     fn language_name(&self) -> &'static str;
@@ -95,6 +113,7 @@ pub struct Tags;
 // TODO: hide in docs?
 trait InstantiateHelper<Type> {
     fn instantiate(&self, search: Box<str>) -> Result<QueryFunction, InstantiationError>;
+    fn query_source(&self, search: &str) -> String;
 }
 
 // TODO: hide in docs?
@@ -130,6 +149,10 @@ impl<T: IdentifierQuery> InstantiateHelper<Identifier> for T {
                     })
             })
     }
+
+    fn query_source(&self, _: &str) -> String {
+        self.query_string().to_string()
+    }
 }
 impl<T: TreeSitterQuery> InstantiateHelper<TreeSitter> for T {
     fn instantiate(&self, search: Box<str>) -> Result<QueryFunction, InstantiationError> {
@@ -148,6 +171,10 @@ impl<T: TreeSitterQuery> InstantiateHelper<TreeSitter> for T {
         })
         .map_err(InstantiationError::Query)
     }
+
+    fn query_source(&self, search: &str) -> String {
+        self.query_string_function(search)
+    }
 }
 struct TagsConfigurationThreadSafe(TagsConfiguration);
 unsafe impl Send for TagsConfigurationThreadSafe {}
@@ -209,24 +236,40 @@ impl<T: TreeSitterTags> InstantiateHelper<Tags> for T {
                 })
             })
     }
+
+    fn query_source(&self, _: &str) -> String {
+        self.tag_query().to_string()
+    }
 }
 
 impl<T: Assoc + InstantiateHelper<T::Type> + HasLanguageInformation> SupportedLanguage for T {
     fn instantiate(&self, search: Box<str>) -> Result<QueryFunction, InstantiationError> {
         self.instantiate(search)
     }
+
+    fn query_source(&self, search: &str) -> String {
+        self.query_source(search)
+    }
 }
 // TODO: maybe make this fallable
 type QueryFunction = Box<dyn for<'x, 'y> Fn(Node<'x>, &'y [u8]) -> Box<[Range]> + Send + Sync>;
 
 pub trait SupportedLanguage: HasLanguageInformation {
     fn instantiate(&self, search: Box<str>) -> Result<QueryFunction, InstantiationError>;
+    /// The text of the query used when searching for `search`, it is used to tell when results
+    /// kept from an earlier search (like in a cache) are out of date.
+    ///
+    /// By default it is empty, so the results of this language are never kept.
+    fn query_source(&self, _search: &str) -> String {
+        String::new()
+    }
     fn to_language<'a>(
         &self,
         search: &'a str,
     ) -> Result<InstantiatedLanguage<'a>, InstantiationError> {
+        let query = self.query_source(search);
         self.instantiate(search.into())
-            .map(|f| InstantiatedLanguage::new(search, self.language_info(), f))
+            .map(|f| InstantiatedLanguage::new(search, self.language_info(), f, &query))
     }
 }
 
@@ -253,11 +296,24 @@ impl<'a> InstantiatedLanguage<'a> {
         search_name: &'a str,
         language: LanguageInformation,
         run_query: QueryFunction,
+        query: &str,
     ) -> Self {
+        let mut hasher = DefaultHasher::new();
+        query.hash(&mut hasher);
+        // without the query there is nothing to tell when the results could have changed
+        let grammar = &language.language;
+        (
+            grammar.version(),
+            grammar.node_kind_count(),
+            grammar.parse_state_count(),
+            grammar.field_count(),
+        )
+            .hash(&mut hasher);
         Self {
             search_name,
             language,
             run_query,
+            fingerprint: (!query.is_empty()).then(|| hasher.finish()),
         }
     }
 
@@ -280,6 +336,30 @@ impl<'a> InstantiatedLanguage<'a> {
         &self.language.language
     }
 
+    /// Finds the (named) node kind called [`kind`] in the grammar of this language, so it can be
+    /// compared with the kinds of nodes (like from [`crate::ParsedFile::result_kinds`]).
+    #[must_use]
+    pub fn node_kind(&self, kind: &str) -> Option<&'static str> {
+        let grammar = self.language();
+        grammar
+            .node_kind_for_id(grammar.id_for_node_kind(kind, true))
+            .filter(|found| *found == kind)
+    }
+
+    /// A hash of the query and the grammar used for searching, when either of them changes the
+    /// results of a search could change too, so results kept from before (like in a cache) should
+    /// not be used anymore.
+    ///
+    /// The hash is not stable between versions of rust, so it can also change when nothing else
+    /// did.
+    ///
+    /// `None` if the language does not give its query (see [`SupportedLanguage::query_source`]),
+    /// then its results should not be kept.
+    #[must_use]
+    pub const fn fingerprint(&self) -> Option<u64> {
+        self.fingerprint
+    }
+
     pub(crate) const fn search_name(&self) -> &str {
         self.search_name
     }
@@ -1,21 +1,35 @@
 use std::{
-    sync::mpsc::{Receiver, RecvTimeoutError, Sender},
+    path::PathBuf,
+    sync::{
+        mpsc::{Receiver, RecvTimeoutError, Sender},
+        Mutex,
+    },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use git_function_history::get_function_history;
+use git_function_history::{Commit, Error, FunctionHistory, ProgressSink};
 use types::{FullCommand, SearchType};
 
 use crate::types::{CommandResult, ListType, Status};
 
 pub mod types;
 
+/// How often the commits found so far are sent while a search is running.
+const PARTIAL_RESULTS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often the progress of a search is sent, the frontends only handle one message per frame so we can't send every update.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
 /// the thread that handles the commands
+///
+/// if `repo` is given, all commands are run in that repository (unless a search specifies its own `SearchOptions::repo`),
+/// otherwise in the repository the current directory is in
 pub fn command_thread(
     rx_t: Receiver<FullCommand>,
     tx_t: Sender<(CommandResult, Status)>,
     log: bool,
+    repo: Option<PathBuf>,
 ) {
     thread::spawn(move || loop {
         match rx_t.recv_timeout(Duration::from_millis(100)) {
@@ -33,88 +47,139 @@ pub fn command_thread(
                 }
             },
             Ok(msg) => {
-                let now = std::time::Instant::now();
+                let now = Instant::now();
                 let msg = match msg {
-                    FullCommand::List(list_type) => {
+                    FullCommand::List(list_type, mut options) => {
+                        if options.repo.is_none() {
+                            options.repo.clone_from(&repo);
+                        }
                         if log {
-                            log::info!("list");
+                            log::info!("list {list_type} with options {options:?}");
                         }
                         match list_type {
-                            ListType::Commits => match git_function_history::get_git_info() {
-                                Ok(commits) => {
-                                    if log {
-                                        log::info!("found {} commits", commits.len());
+                            ListType::Commits => {
+                                match git_function_history::get_git_info_with_options(&options) {
+                                    Ok(commits) => {
+                                        if log {
+                                            log::info!("found {} commits", commits.len());
+                                        }
+                                        let commits =
+                                            commits.iter().map(|c| c.hash.to_string()).collect();
+                                        (
+                                            CommandResult::String(commits),
+                                            Status::Ok(Some(format!(
+                                                "Found commits dates took {}s",
+                                                now.elapsed().as_secs()
+                                            ))),
+                                        )
                                     }
-                                    let commits =
-                                        commits.iter().map(|c| c.hash.to_string()).collect();
-                                    (
-                                        CommandResult::String(commits),
-                                        Status::Ok(Some(format!(
-                                            "Found commits dates took {}s",
+                                    Err(err) => (
+                                        CommandResult::None,
+                                        Status::Error(format!(
+                                            "Error getting commits: {} took {}s",
+                                            err,
                                             now.elapsed().as_secs()
-                                        ))),
-                                    )
+                                        )),
+                                    ),
                                 }
-                                Err(err) => (
-                                    CommandResult::None,
-                                    Status::Error(format!(
-                                        "Error getting commits: {} took {}s",
-                                        err,
-                                        now.elapsed().as_secs()
-                                    )),
-                                ),
-                            },
-                            ListType::Dates => match git_function_history::get_git_info() {
-                                Ok(dates) => {
-                                    if log {
-                                        log::info!("found {} dates", dates.len());
+                            }
+                            ListType::Dates => {
+                                match git_function_history::get_git_info_with_options(&options) {
+                                    Ok(dates) => {
+                                        if log {
+                                            log::info!("found {} dates", dates.len());
+                                        }
+                                        let dates =
+                                            dates.iter().map(|d| d.date.to_rfc2822()).collect();
+                                        (
+                                            CommandResult::String(dates),
+                                            Status::Ok(Some(format!(
+                                                "Found dates took {}s",
+                                                now.elapsed().as_secs()
+                                            ))),
+                                        )
                                     }
-                                    let dates = dates.iter().map(|d| d.date.to_rfc2822()).collect();
-                                    (
-                                        CommandResult::String(dates),
-                                        Status::Ok(Some(format!(
-                                            "Found dates took {}s",
+                                    Err(err) => (
+                                        CommandResult::None,
+                                        Status::Error(format!(
+                                            "Error getting dates: {} took {}s",
+                                            err,
                                             now.elapsed().as_secs()
-                                        ))),
-                                    )
+                                        )),
+                                    ),
                                 }
-                                Err(err) => (
-                                    CommandResult::None,
-                                    Status::Error(format!(
-                                        "Error getting dates: {} took {}s",
-                                        err,
-                                        now.elapsed().as_secs()
-                                    )),
-                                ),
-                            },
+                            }
                         }
                     }
                     FullCommand::Search(SearchType {
                         search: name,
                         file,
                         filter,
+                        mut options,
                     }) => {
+                        if options.repo.is_none() {
+                            options.repo.clone_from(&repo);
+                        }
+                        if options.progress.is_none() {
+                            options.progress = Some(progress_sink(tx_t.clone()));
+                        }
                         if log {
                             log::info!(
-                                "Searching for {} in {:?} and filter {:?}",
+                                "Searching for {} in {:?} and filter {:?} with options {:?}",
                                 name,
                                 file,
-                                filter
+                                filter,
+                                options
                             );
                         }
-                        match get_function_history!(name = &name, file = file, filter = filter) {
-                            Ok(functions) => {
+                        let mut commits = vec![];
+                        // how many of the commits were already sent
+                        let mut sent = 0;
+                        let mut last_sent = Instant::now();
+                        let result = git_function_history::stream_function_history(
+                            &name,
+                            &file,
+                            &filter,
+                            function_grep::supported_languages::predefined_languages(),
+                            &options,
+                            |commit| {
+                                commits.push(commit);
+                                // every so often send what was found so far, so it can be shown while the search is still running
+                                // (only the ones found since the last time, so the frontend can keep its place in the history)
+                                if last_sent.elapsed() >= PARTIAL_RESULTS_INTERVAL {
+                                    last_sent = Instant::now();
+                                    let found =
+                                        found_since(&name, commits[sent..].to_vec(), sent == 0);
+                                    sent = commits.len();
+                                    let _ = tx_t.send((found, Status::Loading));
+                                }
+                            },
+                        );
+                        match result {
+                            Ok(()) => {
                                 if log {
                                     log::info!("Found functions");
                                 }
                                 (
-                                    CommandResult::History(functions),
+                                    found_since(&name, commits.split_off(sent), sent == 0),
                                     Status::Ok(Some(format!(
                                         "Found functions took {}s",
                                         now.elapsed().as_secs()
                                     ))),
                                 )
                             }
+                            // what was found before the search was cancelled is still shown
+                            Err(Error::Cancelled) => {
+                                if log {
+                                    log::info!("Search cancelled");
+                                }
+                                let found = if commits.is_empty() {
+                                    CommandResult::None
+                                } else {
+                                    found_since(&name, commits.split_off(sent), sent == 0)
+                                };
+                                (found, Status::Ok(Some("search cancelled".to_string())))
+                            }
                             Err(err) => (
                                 CommandResult::None,
                                 Status::Error(format!(
@@ -176,3 +241,26 @@ pub fn command_thread(
         }
     });
 }
+
+/// what a search found since it last sent what it found, the first time as a new history and then as more commits for it
+fn found_since(name: &str, commits: Vec<Commit>, first: bool) -> CommandResult {
+    if first {
+        CommandResult::History(FunctionHistory::new(name.to_string(), commits))
+    } else {
+        CommandResult::MoreCommits(commits)
+    }
+}
+
+/// sends the progress of a search as `Status::Progress` (at most once every `PROGRESS_INTERVAL`)
+fn progress_sink(tx_t: Sender<(CommandResult, Status)>) -> ProgressSink {
+    let last_sent = Mutex::new(None::<Instant>);
+    ProgressSink::new(move |progress| {
+        let Ok(mut last_sent) = last_sent.lock() else {
+            return;
+        };
+        if progress.done() || last_sent.is_none_or(|sent| sent.elapsed() >= PROGRESS_INTERVAL) {
+            *last_sent = Some(Instant::now());
+            let _ = tx_t.send((CommandResult::None, Status::Progress(progress)));
+        }
+    })
+}
@@ -1,7 +1,9 @@
 use std::fmt;
 
 use function_grep::filter::InstantiatedFilterType;
-use git_function_history::{FileFilterType, Filter, FunctionHistory};
+use git_function_history::{
+    Commit, FileFilterType, Filter, FunctionHistory, Progress, SearchOptions,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Command {
@@ -40,6 +42,8 @@ impl std::fmt::Display for ListType {
 #[derive(Debug, Clone, Default)]
 pub enum CommandResult {
     History(FunctionHistory),
+    /// the commits a running search found since it last sent what it found, to be added to the end of the history it sent first
+    MoreCommits(Vec<Commit>),
     String(Vec<String>),
     #[default]
     None,
@@ -49,6 +53,7 @@ impl CommandResult {
     pub fn len(&self) -> usize {
         match self {
             CommandResult::History(history) => history.to_string().split('\n').count(),
+            CommandResult::MoreCommits(_) => 1,
             CommandResult::String(str) => str.len(),
             CommandResult::None => 0,
         }
@@ -61,6 +66,7 @@ impl fmt::Display for CommandResult {
             CommandResult::History(history) => {
                 write!(f, "{history}")
             }
+            CommandResult::MoreCommits(commits) => write!(f, "{} more commits", commits.len()),
             CommandResult::String(string) => {
                 for line in string {
                     writeln!(f, "{line}")?;
@@ -79,6 +85,8 @@ pub enum Status {
     Error(String),
     Warning(String),
     Loading,
+    /// a search is running, and this is how far along it is
+    Progress(Progress),
 }
 impl fmt::Display for Status {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -90,6 +98,7 @@ impl fmt::Display for Status {
             Status::Error(s) => write!(f, "Err {s}"),
             Status::Warning(s) => write!(f, "Warn {s}"),
             Status::Loading => write!(f, "Loading..."),
+            Status::Progress(progress) => write!(f, "Searching... {progress}"),
         }
     }
 }
@@ -102,7 +111,8 @@ impl Default for Status {
 //#[derive(Debug, Clone)]
 pub enum FullCommand {
     Filter(FilterType),
-    List(ListType),
+    /// the options say which repository and revision to list the commits of
+    List(ListType, SearchOptions),
     Search(SearchType),
 }
 
@@ -111,6 +121,7 @@ pub struct SearchType {
     pub search: String,
     pub file: FileFilterType,
     pub filter: Filter,
+    pub options: SearchOptions,
 }
 
 impl SearchType {
@@ -119,6 +130,7 @@ impl SearchType {
             search,
             file: file_filter,
             filter,
+            options: SearchOptions::default(),
         }
     }
 
@@ -127,8 +139,14 @@ impl SearchType {
             search: tuple.0,
             file: tuple.1,
             filter: tuple.2,
+            options: SearchOptions::default(),
         }
     }
+
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        self.options = options;
+        self
+    }
 }
 
 //#[derive(Debug, Clone)]
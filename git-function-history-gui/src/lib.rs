@@ -4,15 +4,21 @@ use std::{collections::HashMap, fmt, sync::mpsc, time::Duration};
 
 use eframe::{
     self,
-    egui::{self, Button, Label, Layout, Sense, SidePanel, TextEdit, TopBottomPanel, Visuals},
+    egui::{
+        self, Button, Label, Layout, ProgressBar, Sense, SidePanel, TextEdit, TopBottomPanel,
+        Visuals,
+    },
     epaint::{Color32, Vec2},
 };
 use function_history_backend_thread::types::{
     Command, CommandResult, FilterType, FullCommand, ListType, SearchType, Status,
 };
-use git_function_history::{types::Directions, Commit, FileFilterType, Filter, FunctionHistory};
+use git_function_history::{
+    types::Directions, CancellationToken, Commit, FileFilterType, Filter, FunctionHistory,
+    MergeMode, Pickaxe, SearchOptions,
+};
 use itertools::Itertools;
-use types::{HistoryFilterType, PLFilter};
+use types::{Combinator, HistoryFilterType, PLFilter};
 
 // TODO: stop cloning everyting and use references instead
 pub struct MyEguiApp {
@@ -28,9 +34,26 @@ pub struct MyEguiApp {
     ),
     filter: Filter,
     file_type: FileFilterType,
+    search_options: SearchOptions,
+    revision: String,
+    // the patterns of `SearchOptions::exclude`, separated by spaces
+    exclude: String,
+    // the string (or regex) of `SearchOptions::pickaxe`, no pickaxe if it is empty
+    pickaxe: String,
+    pickaxe_regex: bool,
+    // cancels the last search that was started
+    cancel: CancellationToken,
     history_filter_type: types::HistoryFilterType,
+    // negates the filter that is being built
+    negate_filter: bool,
+    // compares the dates of the filter with the local time of the commits
+    local_time_filter: bool,
+    // the filters that were added with the and/or buttons, and how the next one gets combined with them
+    combined_filter: Option<(Filter, Combinator)>,
     current_commit: String,
     do_commit: bool,
+    // the commit hash (prefix) or date to go to in the history
+    go_to: String,
 }
 
 impl MyEguiApp {
@@ -51,9 +74,19 @@ impl MyEguiApp {
             channels,
             file_type: FileFilterType::None,
             filter: Filter::None,
+            search_options: SearchOptions::default(),
+            revision: String::new(),
+            exclude: String::new(),
+            pickaxe: String::new(),
+            pickaxe_regex: false,
+            cancel: CancellationToken::new(),
             history_filter_type: types::HistoryFilterType::None,
+            negate_filter: false,
+            local_time_filter: false,
+            combined_filter: None,
             current_commit: String::new(),
             do_commit: false,
+            go_to: String::new(),
         }
     }
 
@@ -78,6 +111,9 @@ impl MyEguiApp {
                     "Date: {}",
                     commit.get_metadata()["date"]
                 )));
+                if let Some(old_name) = commit.renamed_from() {
+                    ui.add(Label::new(format!("Renamed from: {old_name}")));
+                }
             });
         }
         TopBottomPanel::top("file_name").show(ctx, |ui| {
@@ -186,7 +222,12 @@ impl MyEguiApp {
         }
     }
 
-    fn draw_history(history: &mut FunctionHistory, ctx: &egui::Context) {
+    fn draw_history(
+        history: &mut FunctionHistory,
+        go_to: &mut String,
+        status: &mut Status,
+        ctx: &egui::Context,
+    ) {
         // split the screen top and bottom into two parts, leave small part for the left arrow commit hash and right arrow and the rest for the content
         // create a 3 line header
         TopBottomPanel::top("control history").show(ctx, |ui| {
@@ -241,6 +282,33 @@ impl MyEguiApp {
                     }
                 }
             });
+            ui.horizontal(|ui| {
+                if ui.button("first").clicked() {
+                    history.go_to_first();
+                }
+                if ui.button("last").clicked() {
+                    history.go_to_last();
+                }
+                ui.add(TextEdit::singleline(go_to).hint_text("commit hash or date"));
+                if ui.button("go to").clicked() {
+                    // anything that is not the start of a commit hash is tried as a date
+                    let moved = match history.go_to_commit(go_to) {
+                        Err(git_function_history::Error::CommitNotFound(hash)) => history
+                            .go_to_date(go_to)
+                            .map_err(|_| git_function_history::Error::CommitNotFound(hash)),
+                        moved => moved,
+                    };
+                    *status = match moved {
+                        Ok(()) => Status::Ok(Some(format!(
+                            "commit {} of {}",
+                            history.current_index() + 1,
+                            history.len()
+                        ))),
+                        Err(e) => Status::Error(e.to_string()),
+                    };
+                }
+                ui.label(format!("{}/{}", history.current_index() + 1, history.len()));
+            });
         });
         if let Some(x) = history.get_mut_commit() {
             Self::draw_commit(x, ctx, false)
@@ -316,6 +384,83 @@ macro_rules! draw_text_input {
 macro_rules! draw_selecction {
     () => {};
 }
+impl MyEguiApp {
+    /// the filter that is being built in the filter command, `None` if no filter type was picked
+    fn history_filter(&self) -> Result<Option<Filter>, String> {
+        Ok(Some(match &self.history_filter_type {
+            HistoryFilterType::Date(date) => Filter::Date(date.to_string()),
+            HistoryFilterType::CommitHash(commit_hash) => {
+                Filter::CommitHash(commit_hash.to_string())
+            }
+            HistoryFilterType::DateRange(date1, date2) => {
+                Filter::DateRange(date1.to_string(), date2.to_string())
+            }
+            HistoryFilterType::FileAbsolute(file) => Filter::FileAbsolute(file.to_string()),
+            HistoryFilterType::FileRelative(file) => Filter::FileRelative(file.to_string()),
+            HistoryFilterType::Directory(dir) => Filter::Directory(dir.to_string()),
+            HistoryFilterType::FileGlob(glob) => Filter::FileGlob(glob.to_string()),
+            HistoryFilterType::FileRegex(regex) => Filter::FileRegex(regex.to_string()),
+            HistoryFilterType::None => return Ok(None),
+            HistoryFilterType::PL(filter) => {
+                let filter = match filter {
+                    types::PLFilter::Single(input, filter) => {
+                        instantiate_filter(filter, input.iter())
+                    }
+
+                    types::PLFilter::Many(input, filter, _, _, _) => {
+                        instantiate_filter(filter, input.iter().map(|(k, (_, v))| (k, v)))
+                    }
+                };
+                Filter::PLFilter(filter.map_err(|e| e.to_string())?)
+            }
+        }))
+    }
+
+    /// negates `filter` (if not is checked) and combines it with the filters that were added before it
+    fn combine_filter(&mut self, filter: Filter) -> Filter {
+        let filter = if std::mem::take(&mut self.local_time_filter) {
+            filter.local_time()
+        } else {
+            filter
+        };
+        let filter = if std::mem::take(&mut self.negate_filter) {
+            !filter
+        } else {
+            filter
+        };
+        match self.combined_filter.take() {
+            Some((combined, combinator)) => combinator.combine(combined, filter),
+            None => filter,
+        }
+    }
+
+    /// draws the not checkbox and the and/or buttons, that add `filter` to the combined filters
+    fn draw_filter_combinators(
+        &mut self,
+        ui: &mut egui::Ui,
+        filter: impl FnOnce(&mut Self) -> Option<Filter>,
+    ) {
+        if let Some((combined, combinator)) = &self.combined_filter {
+            ui.add(Label::new(format!("{combined:?} {combinator}")));
+        }
+        ui.checkbox(&mut self.negate_filter, "not");
+        ui.checkbox(&mut self.local_time_filter, "local time");
+        let combinator = if ui.add(Button::new("and")).clicked() {
+            Some(Combinator::And)
+        } else if ui.add(Button::new("or")).clicked() {
+            Some(Combinator::Or)
+        } else {
+            None
+        };
+        if let Some(combinator) = combinator {
+            if let Some(next) = filter(self) {
+                let combined = self.combine_filter(next);
+                self.combined_filter = Some((combined, combinator));
+            }
+        }
+    }
+}
+
 impl eframe::App for MyEguiApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         ctx.request_repaint();
@@ -337,6 +482,21 @@ impl eframe::App for MyEguiApp {
                         match &self.status {
                             Status::Loading => {
                                 ui.colored_label(Color32::BLUE, "Loading...");
+                                if ui.button("Cancel").clicked() {
+                                    self.cancel.cancel();
+                                }
+                            }
+                            Status::Progress(progress) => {
+                                ui.add(
+                                    ProgressBar::new(progress.fraction().unwrap_or(0.0))
+                                        // until all the commits were walked it is not known how far along the search is
+                                        .animate(progress.fraction().is_none())
+                                        .desired_width(300.)
+                                        .text(format!("Searching... {progress}")),
+                                );
+                                if ui.button("Cancel").clicked() {
+                                    self.cancel.cancel();
+                                }
                             }
                             Status::Ok(a) => match a {
                                 Some(a) => {
@@ -445,6 +605,16 @@ impl eframe::App for MyEguiApp {
                                                         HistoryFilterType::Directory(String::new()),
                                                         "directory",
                                                     );
+                                                    ui.selectable_value(
+                                                        &mut self.history_filter_type,
+                                                        HistoryFilterType::FileGlob(String::new()),
+                                                        "file glob",
+                                                    );
+                                                    ui.selectable_value(
+                                                        &mut self.history_filter_type,
+                                                        HistoryFilterType::FileRegex(String::new()),
+                                                        "file regex",
+                                                    );
                                                     ui.selectable_value(
                                                         &mut self.history_filter_type,
                                                         HistoryFilterType::None,
@@ -471,7 +641,9 @@ impl eframe::App for MyEguiApp {
                                                 | HistoryFilterType::CommitHash(dir)
                                                 | HistoryFilterType::FileAbsolute(dir)
                                                 | HistoryFilterType::FileRelative(dir)
-                                                | HistoryFilterType::Directory(dir) => {
+                                                | HistoryFilterType::Directory(dir)
+                                                | HistoryFilterType::FileGlob(dir)
+                                                | HistoryFilterType::FileRegex(dir) => {
                                                     draw_text_input!(ui, max, dir)
                                                 }
                                                 HistoryFilterType::None => {
@@ -504,69 +676,35 @@ impl eframe::App for MyEguiApp {
                                                     }
                                                 },
                                             }
+                                            self.draw_filter_combinators(ui, |this| {
+                                                this.history_filter()
+                                                    .inspect_err(|e| {
+                                                        this.status = Status::Error(e.clone());
+                                                    })
+                                                    .ok()
+                                                    .flatten()
+                                            });
                                             let resp = ui.add(Button::new("Go"));
                                             if resp.clicked() {
                                                 self.status = Status::Loading;
-                                                let filter = match &self.history_filter_type {
-                                                    HistoryFilterType::Date(date) => {
-                                                        Some(Filter::Date(date.to_string()))
-                                                    }
-                                                    HistoryFilterType::CommitHash(commit_hash) => {
-                                                        Some(Filter::CommitHash(
-                                                            commit_hash.to_string(),
-                                                        ))
-                                                    }
-                                                    HistoryFilterType::DateRange(date1, date2) => {
-                                                        Some(Filter::DateRange(
-                                                            date1.to_string(),
-                                                            date2.to_string(),
-                                                        ))
-                                                    }
-                                                    HistoryFilterType::FileAbsolute(file) => {
-                                                        Some(Filter::FileAbsolute(file.to_string()))
-                                                    }
-                                                    HistoryFilterType::FileRelative(file) => {
-                                                        Some(Filter::FileRelative(file.to_string()))
+                                                let filter = match self.history_filter() {
+                                                    Ok(Some(filter)) => {
+                                                        Some(self.combine_filter(filter))
                                                     }
-                                                    HistoryFilterType::Directory(dir) => {
-                                                        Some(Filter::Directory(dir.to_string()))
-                                                    }
-                                                    HistoryFilterType::None => {
-                                                        self.status = Status::Ok(None);
+                                                    Ok(None) => self
+                                                        .combined_filter
+                                                        .take()
+                                                        .map(|(filter, _)| filter),
+                                                    Err(e) => {
+                                                        self.status = Status::Error(e);
                                                         None
                                                     }
-                                                    HistoryFilterType::PL(filter) => {
-                                                        let filter = match filter {
-                                                            types::PLFilter::Single(
-                                                                input,
-                                                                filter,
-                                                            ) => instantiate_filter(
-                                                                filter,
-                                                                input.iter(),
-                                                            ),
-
-                                                            types::PLFilter::Many(
-                                                                input,
-                                                                filter,
-                                                                _,
-                                                                _,
-                                                                _,
-                                                            ) => instantiate_filter(
-                                                                filter,
-                                                                input
-                                                                    .iter()
-                                                                    .map(|(k, (_, v))| (k, v)),
-                                                            ),
-                                                        };
-                                                        filter
-                                                            .inspect_err(|e| {
-                                                                self.status =
-                                                                    Status::Error(e.to_string())
-                                                            })
-                                                            .ok()
-                                                            .map(Filter::PLFilter)
-                                                    }
                                                 };
+                                                if filter.is_none()
+                                                    && matches!(self.status, Status::Loading)
+                                                {
+                                                    self.status = Status::Ok(None);
+                                                }
                                                 if let Some(filter) = filter {
                                                     self.channels
                                                         .0
@@ -592,7 +730,9 @@ impl eframe::App for MyEguiApp {
                                         FileFilterType::Directory(_) => "directory",
                                         FileFilterType::Absolute(_) => "absolute",
                                         FileFilterType::Relative(_) => "relative",
-                                        _ => "file type",
+                                        FileFilterType::Glob(_) => "glob",
+                                        FileFilterType::Regex(_) => "regex",
+                                        FileFilterType::None => "file type",
                                     };
                                     egui::ComboBox::from_id_salt("search_file_combo_box")
                                         .selected_text(text)
@@ -617,15 +757,63 @@ impl eframe::App for MyEguiApp {
                                                 FileFilterType::Directory(String::new()),
                                                 "Directory",
                                             );
+                                            ui.selectable_value(
+                                                &mut self.file_type,
+                                                FileFilterType::Glob(String::new()),
+                                                "Glob",
+                                            );
+                                            ui.selectable_value(
+                                                &mut self.file_type,
+                                                FileFilterType::Regex(String::new()),
+                                                "Regex",
+                                            );
                                         });
                                     match &mut self.file_type {
                                         FileFilterType::None => {}
                                         FileFilterType::Relative(dir)
                                         | FileFilterType::Absolute(dir)
-                                        | FileFilterType::Directory(dir) => {
+                                        | FileFilterType::Directory(dir)
+                                        | FileFilterType::Glob(dir)
+                                        | FileFilterType::Regex(dir) => {
                                             draw_text_input!(ui, max, dir)
                                         }
                                     }
+                                    ui.add(Label::new("Exclude:"));
+                                    draw_text_input!(ui, max, &mut self.exclude);
+                                    if let FileFilterType::Absolute(_) = self.file_type {
+                                        ui.checkbox(&mut self.search_options.follow, "follow");
+                                    }
+                                    ui.checkbox(
+                                        &mut self.search_options.follow_function_renames,
+                                        "follow renames",
+                                    );
+                                    ui.checkbox(&mut self.search_options.disk_cache, "cache");
+                                    egui::ComboBox::from_id_salt("search_merges_combo_box")
+                                        .selected_text(match self.search_options.merges {
+                                            MergeMode::All => "all commits",
+                                            MergeMode::FirstParent => "first parent",
+                                            MergeMode::SkipMerges => "no merges",
+                                            MergeMode::MergesOnly => "only merges",
+                                        })
+                                        .show_ui(ui, |ui| {
+                                            for (merges, text) in [
+                                                (MergeMode::All, "all commits"),
+                                                (MergeMode::FirstParent, "first parent"),
+                                                (MergeMode::SkipMerges, "no merges"),
+                                                (MergeMode::MergesOnly, "only merges"),
+                                            ] {
+                                                ui.selectable_value(
+                                                    &mut self.search_options.merges,
+                                                    merges,
+                                                    text,
+                                                );
+                                            }
+                                        });
+                                    ui.add(Label::new("Revision:"));
+                                    draw_text_input!(ui, max, &mut self.revision);
+                                    ui.add(Label::new("Pickaxe:"));
+                                    draw_text_input!(ui, max, &mut self.pickaxe);
+                                    ui.checkbox(&mut self.pickaxe_regex, "regex");
                                     // get filters if any
                                     let text = match &self.filter {
                                         Filter::CommitHash(_) => "commit hash".to_string(),
@@ -697,16 +885,61 @@ impl eframe::App for MyEguiApp {
                                     //            "All",
                                     //        );
                                     //    });
+                                    self.draw_filter_combinators(
+                                        ui,
+                                        |this| match std::mem::replace(
+                                            &mut this.filter,
+                                            Filter::None,
+                                        ) {
+                                            Filter::None => None,
+                                            filter => Some(filter),
+                                        },
+                                    );
                                     let resp = ui.add(Button::new("Go"));
                                     if resp.clicked() {
                                         self.status = Status::Loading;
+                                        self.cancel = CancellationToken::new();
+                                        let filter =
+                                            std::mem::replace(&mut self.filter, Filter::None);
+                                        let filter = match filter {
+                                            Filter::None => self
+                                                .combined_filter
+                                                .take()
+                                                .map_or(Filter::None, |(filter, _)| filter),
+                                            filter => self.combine_filter(filter),
+                                        };
                                         self.channels
                                             .0
-                                            .send(FullCommand::Search(SearchType::new(
-                                                self.input_buffer.clone(),
-                                                self.file_type.clone(),
-                                                std::mem::replace(&mut self.filter, Filter::None),
-                                            )))
+                                            .send(FullCommand::Search(
+                                                SearchType::new(
+                                                    self.input_buffer.clone(),
+                                                    self.file_type.clone(),
+                                                    filter,
+                                                )
+                                                .with_options(SearchOptions {
+                                                    // no revision means HEAD
+                                                    revision: (!self.revision.is_empty())
+                                                        .then(|| self.revision.clone()),
+                                                    cancel: Some(self.cancel.clone()),
+                                                    exclude: self
+                                                        .exclude
+                                                        .split_whitespace()
+                                                        .map(str::to_string)
+                                                        .collect(),
+                                                    pickaxe: (!self.pickaxe.is_empty()).then(
+                                                        || {
+                                                            if self.pickaxe_regex {
+                                                                Pickaxe::Regex(self.pickaxe.clone())
+                                                            } else {
+                                                                Pickaxe::Literal(
+                                                                    self.pickaxe.clone(),
+                                                                )
+                                                            }
+                                                        },
+                                                    ),
+                                                    ..self.search_options.clone()
+                                                }),
+                                            ))
                                             .expect("could not send message in thread");
                                     }
                                 }
@@ -725,12 +958,22 @@ impl eframe::App for MyEguiApp {
                                                 "commits",
                                             );
                                         });
+                                    ui.add(Label::new("Revision:"));
+                                    draw_text_input!(ui, max, &mut self.revision);
                                     let resp = ui.add(Button::new("Go"));
                                     if resp.clicked() {
                                         self.status = Status::Loading;
                                         self.channels
                                             .0
-                                            .send(FullCommand::List(self.list_type))
+                                            .send(FullCommand::List(
+                                                self.list_type,
+                                                SearchOptions {
+                                                    // no revision means HEAD
+                                                    revision: (!self.revision.is_empty())
+                                                        .then(|| self.revision.clone()),
+                                                    ..SearchOptions::default()
+                                                },
+                                            ))
                                             .expect("could not send message in thread");
                                     }
                                 }
@@ -753,11 +996,29 @@ impl eframe::App for MyEguiApp {
                             log::warn!("{}", e);
                             self.status = Status::Error(e);
                         }
+                        // more of what a search found, the history stays at the commit that is shown
+                        (CommandResult::MoreCommits(commits), status) => {
+                            log::trace!("got more results of last command");
+                            if let CommandResult::History(history) = &mut self.cmd_output {
+                                history.extend(commits);
+                            }
+                            if let Status::Ok(msg) = status {
+                                self.status = Status::Ok(msg);
+                            }
+                        }
                         (t, Status::Ok(msg)) => {
                             log::info!("got results of last command");
                             self.status = Status::Ok(msg);
                             self.cmd_output = t;
                         }
+                        (_, Status::Progress(progress)) => {
+                            self.status = Status::Progress(progress);
+                        }
+                        // what a search found so far, while it is still running
+                        (t @ CommandResult::History(_), Status::Loading) => {
+                            log::trace!("got partial results of last command");
+                            self.cmd_output = t;
+                        }
                         _ => {}
                     },
                     Err(e) => match e {
@@ -770,7 +1031,7 @@ impl eframe::App for MyEguiApp {
                 // match self.commmand and render based on that
                 match &mut self.cmd_output {
                     CommandResult::History(t) => {
-                        Self::draw_history(t, ctx);
+                        Self::draw_history(t, &mut self.go_to, &mut self.status, ctx);
                     }
 
                     CommandResult::String(t) => {
@@ -811,8 +1072,8 @@ impl eframe::App for MyEguiApp {
                             self.do_commit = true;
                         }
                     }
-                    CommandResult::None => match &self.status {
-                        Status::Loading => {
+                    CommandResult::None | CommandResult::MoreCommits(_) => match &self.status {
+                        Status::Loading | Status::Progress(_) => {
                             ui.add(Label::new("Loading..."));
                         }
                         _ => {
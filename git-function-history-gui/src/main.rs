@@ -5,7 +5,11 @@ use eframe::{
 };
 use git_function_history_gui::MyEguiApp;
 use image::ImageFormat::Png;
-use std::sync::{mpsc, Arc};
+use std::{
+    env,
+    path::PathBuf,
+    sync::{mpsc, Arc},
+};
 fn main() -> eframe::Result<()> {
     let (tx_t, rx_m) = mpsc::channel();
     let (tx_m, rx_t) = mpsc::channel();
@@ -14,7 +18,11 @@ fn main() -> eframe::Result<()> {
     const ICON: &[u8] = include_bytes!("../resources/icon1.png");
     let icon =
         image::load_from_memory_with_format(ICON, Png).expect("could not load image for icon");
-    function_history_backend_thread::command_thread(rx_t, tx_t, true);
+    // the only argument is which repository to use (defaults to the one in the current directory)
+    let repo = env::args()
+        .skip(1)
+        .find_map(|arg| arg.strip_prefix("--repo=").map(PathBuf::from));
+    function_history_backend_thread::command_thread(rx_t, tx_t, true, repo);
     let native_options = eframe::NativeOptions {
         viewport: ViewportBuilder::default()
             .with_icon(Arc::new(IconData {
@@ -8,12 +8,43 @@ pub enum HistoryFilterType {
     FileAbsolute(String),
     FileRelative(String),
     Directory(String),
+    FileGlob(String),
+    FileRegex(String),
     // if filter type is a many and it has more than on filter
     // 1. if you can pick which language to use
     // 2. or you can add or remove a field to apply to the filter
     PL(PLFilter),
     None,
 }
+/// How the filter that is being built gets combined with the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combinator {
+    And,
+    Or,
+}
+
+impl Combinator {
+    pub fn combine(
+        self,
+        filter: git_function_history::Filter,
+        next: git_function_history::Filter,
+    ) -> git_function_history::Filter {
+        match self {
+            Self::And => filter.and(next),
+            Self::Or => filter.or(next),
+        }
+    }
+}
+
+impl fmt::Display for Combinator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::And => write!(f, "and"),
+            Self::Or => write!(f, "or"),
+        }
+    }
+}
+
 pub enum PLFilter {
     Single(HashMap<String, String>, FilterType<'static>),
     Many(
@@ -45,6 +76,8 @@ impl PartialEq for HistoryFilterType {
             (Self::FileAbsolute(l0), Self::FileAbsolute(r0)) => l0 == r0,
             (Self::FileRelative(l0), Self::FileRelative(r0)) => l0 == r0,
             (Self::Directory(l0), Self::Directory(r0)) => l0 == r0,
+            (Self::FileGlob(l0), Self::FileGlob(r0)) => l0 == r0,
+            (Self::FileRegex(l0), Self::FileRegex(r0)) => l0 == r0,
             (Self::PL(l0), Self::PL(r0)) => l0.filter_name() == r0.filter_name(),
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
@@ -64,6 +97,8 @@ impl fmt::Debug for HistoryFilterType {
             Self::FileAbsolute(arg0) => f.debug_tuple("FileAbsolute").field(arg0).finish(),
             Self::FileRelative(arg0) => f.debug_tuple("FileRelative").field(arg0).finish(),
             Self::Directory(arg0) => f.debug_tuple("Directory").field(arg0).finish(),
+            Self::FileGlob(arg0) => f.debug_tuple("FileGlob").field(arg0).finish(),
+            Self::FileRegex(arg0) => f.debug_tuple("FileRegex").field(arg0).finish(),
             Self::PL(filter) => f.debug_tuple("PL").field(&filter.filter_name()).finish(),
             Self::None => write!(f, "None"),
         }
@@ -79,6 +114,8 @@ impl fmt::Display for HistoryFilterType {
             HistoryFilterType::FileAbsolute(_) => write!(f, "file absolute"),
             HistoryFilterType::FileRelative(_) => write!(f, "file relative"),
             HistoryFilterType::Directory(_) => write!(f, "directory"),
+            HistoryFilterType::FileGlob(_) => write!(f, "file glob"),
+            HistoryFilterType::FileRegex(_) => write!(f, "file regex"),
             HistoryFilterType::PL(pl) => write!(f, "{}", pl.filter_name()),
             HistoryFilterType::None => write!(f, "none"),
         }
@@ -0,0 +1,448 @@
+//! Caches of what was found in each blob (and tree), so a blob that is in many commits only has to be parsed once.
+//!
+//! During a search every blob and tree that was searched is kept in memory.
+//! With the `cache` feature and `SearchOptions::disk_cache` where the function was found in each blob is also kept on disk between searches,
+//! so files that did not change since the last search do not have to be parsed again.
+//!
+//! The disk cache is stored in the git directory under `function-history-cache/v<format version>`,
+//! with one file for each search name and language.
+//! The first line of a file is the fingerprint of the query and grammar it was made with (see `InstantiatedLanguage::fingerprint`),
+//! if it does not match the one used for the search the file is ignored (and replaced once the search is done).
+//! Languages without a fingerprint are not kept on disk.
+//! Each other line is a blob id followed by the ranges of the function in that blob (if any were found),
+//! each with the tree sitter node kind of the function.
+
+use crate::FileFilterType;
+use function_grep::{supported_languages::InstantiatedLanguage, ParsedFile, Range};
+use gix::ObjectId;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+};
+
+#[cfg(feature = "cache")]
+use function_grep::Point;
+#[cfg(feature = "cache")]
+use log::{info, warn};
+#[cfg(feature = "cache")]
+use std::{fmt::Write as _, fs, path::PathBuf, process, sync::Mutex};
+
+/// The version of the format of the disk cache, it is part of the path of the cache,
+/// so caches written in another format are never read.
+#[cfg(feature = "cache")]
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Counts the temporary files written by this process, to give each one its own name.
+#[cfg(feature = "cache")]
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "cache")]
+struct LanguageCache {
+    fingerprint: Option<u64>,
+    found: HashMap<ObjectId, Found>,
+    // whether blobs were added during this search, so the file has to be written again
+    changed: bool,
+}
+
+/// Where the function was found in a blob (no ranges means that the function is not in that blob),
+/// with the tree sitter node kind of the function at each range.
+#[derive(Debug, Clone, Default)]
+pub struct Found {
+    pub ranges: Box<[Range]>,
+    pub kinds: Box<[&'static str]>,
+}
+
+impl Found {
+    pub fn of(file: &ParsedFile) -> Self {
+        Self {
+            ranges: file.results().into(),
+            kinds: file.result_kinds().into(),
+        }
+    }
+}
+
+/// How many blobs had to be parsed during a search, and how many times the caches saved us some work.
+/// Can be shared between the caches for different names (when following function renames).
+#[derive(Debug, Default)]
+pub struct Stats {
+    parsed_blobs: AtomicUsize,
+    hits: AtomicUsize,
+}
+
+impl Stats {
+    pub fn parsed_blobs(&self) -> usize {
+        self.parsed_blobs.load(Ordering::Relaxed)
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The caches for one search (for one function name, a batch search has one for each name).
+pub struct Cache {
+    // the result of each blob that was already searched (per language), shared between all the commits
+    parsed: RwLock<HashMap<(ObjectId, &'static str), Option<ParsedFile>>>,
+    // what was found in each (sub)tree that was already searched, as the file names depend on where the tree is,
+    // and the file filter could change between commits (when following a file), those are part of the key as well
+    subtrees: RwLock<HashMap<(ObjectId, String, FileFilterType), Vec<ParsedFile>>>,
+    // only kept when the disk cache is turned on (see `SearchOptions::disk_cache`)
+    disk: Option<DiskCache>,
+    stats: Arc<Stats>,
+    // to read the text of compact files again
+    repo: Arc<gix::ThreadSafeRepository>,
+}
+
+impl Cache {
+    /// Opens the cache for searching for `name` in `repo` (the repository of the search), counting its hits in `stats`.
+    /// The results are only read from and written to disk with `disk`.
+    pub fn open(
+        repo: &Arc<gix::ThreadSafeRepository>,
+        name: &str,
+        stats: Arc<Stats>,
+        disk: bool,
+    ) -> Self {
+        Self {
+            parsed: RwLock::new(HashMap::new()),
+            subtrees: RwLock::new(HashMap::new()),
+            disk: disk.then(|| DiskCache::open(&repo.to_thread_local(), name)),
+            stats,
+            repo: repo.clone(),
+        }
+    }
+
+    /// Makes the file found in the blob compact (see `ParsedFile::compact`),
+    /// so only the found functions are kept and the text of the whole file is read from the blob again when it is needed.
+    pub fn compact(&self, file: ParsedFile, id: ObjectId) -> ParsedFile {
+        let repo = self.repo.clone();
+        file.compact(Arc::new(move || {
+            let repo = repo.to_thread_local();
+            crate::read_blob(id, &repo, &id.to_string())
+                .ok()
+                .map(Into::into)
+        }))
+    }
+
+    /// Counts a blob that was not in any cache, so it had to be parsed.
+    pub fn parsed_blob(&self) {
+        self.stats.parsed_blobs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns what was found in the blob, if the blob was not searched yet during this search it is searched with `search`.
+    pub fn parsed(
+        &self,
+        language: &'static str,
+        id: ObjectId,
+        search: impl FnOnce() -> Option<ParsedFile>,
+    ) -> Option<ParsedFile> {
+        if let Some(parsed) = self.get_parsed(language, id) {
+            return parsed;
+        }
+        let parsed = search();
+        self.insert_parsed(language, id, parsed.clone());
+        parsed
+    }
+
+    /// Returns what was found in the blob (if anything), if the blob was already searched during this search.
+    #[allow(clippy::option_option)]
+    pub fn get_parsed(&self, language: &'static str, id: ObjectId) -> Option<Option<ParsedFile>> {
+        let found = self
+            .parsed
+            .read()
+            .ok()
+            .and_then(|cache| cache.get(&(id, language)).cloned());
+        if found.is_some() {
+            self.stats.hit();
+        }
+        found
+    }
+
+    /// Keeps what was found in the blob for the rest of this search.
+    pub fn insert_parsed(&self, language: &'static str, id: ObjectId, parsed: Option<ParsedFile>) {
+        if let Ok(mut cache) = self.parsed.write() {
+            cache.insert((id, language), parsed);
+        }
+    }
+
+    /// Returns what was found in the tree at `path`, if the tree was not searched yet during this search it is searched with `search`.
+    pub fn subtree<E>(
+        &self,
+        id: ObjectId,
+        path: &str,
+        filter: &FileFilterType,
+        search: impl FnOnce() -> Result<Vec<ParsedFile>, E>,
+    ) -> Result<Vec<ParsedFile>, E> {
+        if let Some(found) = self.get_subtree(id, path, filter) {
+            return Ok(found);
+        }
+        let found = search()?;
+        self.insert_subtree(id, path, filter, found.clone());
+        Ok(found)
+    }
+
+    /// Returns what was found in the tree at `path`, if the tree was already searched during this search.
+    pub fn get_subtree(
+        &self,
+        id: ObjectId,
+        path: &str,
+        filter: &FileFilterType,
+    ) -> Option<Vec<ParsedFile>> {
+        let found = self
+            .subtrees
+            .read()
+            .ok()
+            .and_then(|cache| cache.get(&(id, path.to_string(), filter.clone())).cloned());
+        if found.is_some() {
+            self.stats.hit();
+        }
+        found
+    }
+
+    /// Keeps what was found in the tree at `path` for the rest of this search.
+    pub fn insert_subtree(
+        &self,
+        id: ObjectId,
+        path: &str,
+        filter: &FileFilterType,
+        found: Vec<ParsedFile>,
+    ) {
+        if let Ok(mut cache) = self.subtrees.write() {
+            cache.insert((id, path.to_string(), filter.clone()), found);
+        }
+    }
+
+    /// Returns where the function was found in the blob, if the blob was searched in a previous search.
+    pub fn found(&self, language: &InstantiatedLanguage<'_>, id: ObjectId) -> Option<Found> {
+        let found = self.disk.as_ref()?.get(language, id);
+        if found.is_some() {
+            self.stats.hit();
+        }
+        found
+    }
+
+    /// Keeps where the function was found in the blob for the next searches.
+    pub fn insert_found(&self, language: &InstantiatedLanguage<'_>, id: ObjectId, found: Found) {
+        if let Some(disk) = &self.disk {
+            disk.insert(language, id, found);
+        }
+    }
+}
+
+#[cfg(feature = "cache")]
+struct DiskCache {
+    dir: PathBuf,
+    // each language is only loaded from disk once it is used
+    languages: Mutex<HashMap<&'static str, LanguageCache>>,
+}
+
+#[cfg(feature = "cache")]
+impl DiskCache {
+    fn open(repo: &gix::Repository, name: &str) -> Self {
+        // the name could have anything in it, so we hex encode it to get a valid file name
+        let name = name.bytes().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        });
+        Self {
+            dir: repo
+                .common_dir()
+                .join("function-history-cache")
+                .join(format!("v{FORMAT_VERSION}"))
+                .join(name),
+            languages: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, language: &InstantiatedLanguage<'_>, id: ObjectId) -> Option<Found> {
+        let mut languages = self.languages.lock().ok()?;
+        self.language(&mut languages, language)
+            .found
+            .get(&id)
+            .cloned()
+    }
+
+    fn insert(&self, language: &InstantiatedLanguage<'_>, id: ObjectId, found: Found) {
+        let Ok(mut languages) = self.languages.lock() else {
+            return;
+        };
+        let cache = self.language(&mut languages, language);
+        cache.found.insert(id, found);
+        cache.changed = true;
+    }
+
+    // gets the cache of the language, loading it from disk the first time
+    fn language<'a>(
+        &self,
+        languages: &'a mut HashMap<&'static str, LanguageCache>,
+        language: &InstantiatedLanguage<'_>,
+    ) -> &'a mut LanguageCache {
+        languages
+            .entry(language.name())
+            .or_insert_with(|| LanguageCache {
+                fingerprint: language.fingerprint(),
+                found: self.load(language),
+                changed: false,
+            })
+    }
+
+    fn load(&self, language: &InstantiatedLanguage<'_>) -> HashMap<ObjectId, Found> {
+        language
+            .fingerprint()
+            .and_then(|fingerprint| self.read(language.name(), fingerprint))
+            .map(|saved| {
+                saved
+                    .lines()
+                    .filter_map(|line| parse_line(line, language))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // reads the lines saved for the language, if they were saved with the same fingerprint
+    fn read(&self, language: &str, fingerprint: u64) -> Option<String> {
+        let saved = fs::read_to_string(self.dir.join(language)).ok()?;
+        let (first, lines) = saved.split_once('\n')?;
+        if first != header(fingerprint) {
+            info!(
+                "ignoring the cache for {language} in {}, it is from another query or grammar",
+                self.dir.display()
+            );
+            return None;
+        }
+        Some(lines.to_string())
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Ok(languages) = self.languages.lock() else {
+            return Ok(());
+        };
+        for (language, cache) in languages.iter().filter(|(_, cache)| cache.changed) {
+            let Some(fingerprint) = cache.fingerprint else {
+                continue;
+            };
+            fs::create_dir_all(&self.dir)?;
+            let mut text = header(fingerprint);
+            text.push('\n');
+            // another search could have saved the file since we loaded it, what it found is kept as well
+            let saved = self.read(language, fingerprint).unwrap_or_default();
+            for line in saved.lines().filter(|line| {
+                line.split(' ')
+                    .next()
+                    .and_then(|id| ObjectId::from_hex(id.as_bytes()).ok())
+                    .is_some_and(|id| !cache.found.contains_key(&id))
+            }) {
+                text.push_str(line);
+                text.push('\n');
+            }
+            for (id, found) in &cache.found {
+                text.push_str(&format_line(*id, found));
+            }
+            // the file is written next to the cache and then renamed over it,
+            // so a search running at the same time never reads a half written file
+            let temp = self.dir.join(format!(
+                "{language}.{}.{}.tmp",
+                process::id(),
+                TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+            ));
+            fs::write(&temp, &text)?;
+            if let Err(e) = fs::rename(&temp, self.dir.join(language)) {
+                let _ = fs::remove_file(&temp);
+                return Err(e);
+            }
+            info!(
+                "cached {} blobs for {language} in {}",
+                text.lines().count() - 1,
+                self.dir.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cache")]
+impl Drop for DiskCache {
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            warn!("could not save cache to {}: {e}", self.dir.display());
+        }
+    }
+}
+
+/// Without the `cache` feature nothing is kept on disk.
+#[cfg(not(feature = "cache"))]
+struct DiskCache;
+
+#[cfg(not(feature = "cache"))]
+#[allow(clippy::unused_self)]
+impl DiskCache {
+    const fn open(_: &gix::Repository, _: &str) -> Self {
+        Self
+    }
+
+    const fn get(&self, _: &InstantiatedLanguage<'_>, _: ObjectId) -> Option<Found> {
+        None
+    }
+
+    fn insert(&self, _: &InstantiatedLanguage<'_>, _: ObjectId, _: Found) {}
+}
+
+#[cfg(feature = "cache")]
+fn header(fingerprint: u64) -> String {
+    format!("{fingerprint:016x}")
+}
+
+#[cfg(feature = "cache")]
+fn format_line(id: ObjectId, found: &Found) -> String {
+    let mut line = id.to_string();
+    for (range, kind) in found.ranges.iter().zip(&found.kinds) {
+        let _ = write!(
+            line,
+            " {},{},{},{},{},{},{kind}",
+            range.start_byte,
+            range.end_byte,
+            range.start_point.row,
+            range.start_point.column,
+            range.end_point.row,
+            range.end_point.column
+        );
+    }
+    line.push('\n');
+    line
+}
+
+#[cfg(feature = "cache")]
+fn parse_line(line: &str, language: &InstantiatedLanguage<'_>) -> Option<(ObjectId, Found)> {
+    let mut parts = line.split(' ');
+    let id = ObjectId::from_hex(parts.next()?.as_bytes()).ok()?;
+    let (ranges, kinds) = parts
+        .map(|range| {
+            let (range, kind) = range.rsplit_once(',')?;
+            let mut numbers = range.split(',').map(str::parse::<usize>);
+            let mut next = || numbers.next()?.ok();
+            Some((
+                Range {
+                    start_byte: next()?,
+                    end_byte: next()?,
+                    start_point: Point::new(next()?, next()?),
+                    end_point: Point::new(next()?, next()?),
+                },
+                // the kinds of the grammar live as long as the program, so we use those
+                language.node_kind(kind)?,
+            ))
+        })
+        .collect::<Option<(Vec<_>, Vec<_>)>>()?;
+    Some((
+        id,
+        Found {
+            ranges: ranges.into(),
+            kinds: kinds.into(),
+        },
+    ))
+}
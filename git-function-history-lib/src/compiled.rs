@@ -0,0 +1,64 @@
+//! Filters with their dates already parsed and regexes compiled, so a filter is only parsed once for a whole search (or filtering),
+//! instead of again for every commit (or file).
+
+use chrono::{DateTime, FixedOffset};
+use regex::Regex;
+
+use crate::{
+    dates::{parse_date, DateRange},
+    paths, Error, Filter,
+};
+
+/// A `Filter` with its dates parsed and regexes compiled, the filters that have nothing to parse are kept as they are.
+#[derive(Debug)]
+pub enum CompiledFilter<'a> {
+    Date(DateTime<FixedOffset>),
+    DateRange(DateRange),
+    AuthorDateRange(DateRange),
+    AuthorRegex(Regex),
+    FileRegex(Regex),
+    And(Vec<Self>),
+    Or(Vec<Self>),
+    Not(Box<Self>),
+    LocalTime(Box<Self>),
+    Other(&'a Filter),
+}
+
+impl<'a> CompiledFilter<'a> {
+    /// Parses the dates and compiles the regexes of the filter (and of all the filters in it).
+    ///
+    /// # Errors
+    ///
+    /// If any of the dates cannot be parsed, a date range ends before it starts, or a regex is invalid.
+    pub fn new(filter: &'a Filter) -> Result<Self, Error> {
+        match filter {
+            Filter::And(filters) => filters
+                .iter()
+                .map(Self::new)
+                .collect::<Result<_, _>>()
+                .map(Self::And),
+            Filter::Or(filters) => filters
+                .iter()
+                .map(Self::new)
+                .collect::<Result<_, _>>()
+                .map(Self::Or),
+            Filter::Not(filter) => Ok(Self::Not(Box::new(Self::new(filter)?))),
+            Filter::LocalTime(filter) => Ok(Self::LocalTime(Box::new(Self::new(filter)?))),
+            filter => Self::single(filter),
+        }
+    }
+
+    /// Parses a filter that is not made up of other filters.
+    pub fn single(filter: &'a Filter) -> Result<Self, Error> {
+        Ok(match filter {
+            Filter::Date(date) => Self::Date(parse_date(date)?),
+            Filter::DateRange(start, end) => Self::DateRange(DateRange::parse(start, end)?),
+            Filter::AuthorDateRange(start, end) => {
+                Self::AuthorDateRange(DateRange::parse(start, end)?)
+            }
+            Filter::AuthorRegex(regex) => Self::AuthorRegex(paths::compile_regex(regex)?),
+            Filter::FileRegex(regex) => Self::FileRegex(paths::compile_regex(regex)?),
+            filter => Self::Other(filter),
+        })
+    }
+}
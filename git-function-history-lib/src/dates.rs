@@ -0,0 +1,157 @@
+use chrono::{
+    DateTime, Days, FixedOffset, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc,
+};
+
+use std::ops::Bound;
+
+use crate::Error;
+
+/// The formats of ISO 8601 dates with a time but without a timezone, they are taken to be in UTC.
+const NAIVE_DATE_TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Parses a date of a filter.
+///
+/// The date can be in rfc2822 format (`Mon, 1 Jan 2024 00:00:00 +0000`), ISO 8601 format (`2024-01-01`, `2024-01-01T12:00:00+02:00`),
+/// or relative to now (`now`, `today`, `yesterday`, `2 weeks ago` or `2.weeks.ago`).
+/// Dates without a timezone are in UTC (which is also their local time, see `DateSemantics::Local`).
+pub fn parse_date(date: &str) -> Result<DateTime<FixedOffset>, Error> {
+    parse_date_at(date, Utc::now())
+}
+
+/// Same as `parse_date`, but with relative dates being relative to `now`.
+pub fn parse_date_at(date: &str, now: DateTime<Utc>) -> Result<DateTime<FixedOffset>, Error> {
+    let trimmed = date.trim();
+    if let Some(date) = relative_date(&trimmed.to_lowercase(), now) {
+        return Ok(date.fixed_offset());
+    }
+    DateTime::parse_from_rfc2822(trimmed)
+        .or_else(|_| trimmed.parse::<DateTime<FixedOffset>>())
+        .or_else(|e| {
+            NAIVE_DATE_TIME_FORMATS
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(trimmed, format).ok())
+                .ok_or(e)
+                .map(|date| date.and_utc().fixed_offset())
+        })
+        .or_else(|_| {
+            trimmed
+                .parse::<NaiveDate>()
+                .map(|date| date.and_time(NaiveTime::MIN).and_utc().fixed_offset())
+        })
+        .map_err(|e| Error::InvalidDate(date.to_string(), e))
+}
+
+/// How the dates of the commits are compared with the dates of a filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateSemantics {
+    /// As points in time, so `2024-01-01T01:00:00+02:00` is before `2024-01-01T00:00:00Z`.
+    Utc,
+    /// By the local time of the commits (the date and time of day wherever they were made), ignoring their timezones.
+    Local,
+}
+
+impl DateSemantics {
+    /// the date and time that gets compared
+    pub fn key(self, date: DateTime<FixedOffset>) -> NaiveDateTime {
+        match self {
+            Self::Utc => date.naive_utc(),
+            Self::Local => date.naive_local(),
+        }
+    }
+
+    /// how many seconds `date` and `other` are apart
+    pub fn distance(self, date: DateTime<FixedOffset>, other: DateTime<FixedOffset>) -> i64 {
+        (self.key(date) - self.key(other)).num_seconds().abs()
+    }
+}
+
+/// Parses dates like `yesterday` or `3 days ago` (the words can also be separated by dots like git allows, `3.days.ago`).
+fn relative_date(date: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let today = now.date_naive().and_time(NaiveTime::MIN).and_utc();
+    match date {
+        "now" => return Some(now),
+        "today" => return Some(today),
+        "yesterday" => return today.checked_sub_days(Days::new(1)),
+        _ => {}
+    }
+    let mut words = date
+        .split(|c: char| c.is_whitespace() || c == '.')
+        .filter(|word| !word.is_empty());
+    let (Some(amount), Some(unit), Some("ago"), None) =
+        (words.next(), words.next(), words.next(), words.next())
+    else {
+        return None;
+    };
+    let amount = amount.parse::<u32>().ok()?;
+    match unit.strip_suffix('s').unwrap_or(unit) {
+        "second" => now.checked_sub_signed(TimeDelta::try_seconds(amount.into())?),
+        "minute" => now.checked_sub_signed(TimeDelta::try_minutes(amount.into())?),
+        "hour" => now.checked_sub_signed(TimeDelta::try_hours(amount.into())?),
+        "day" => now.checked_sub_days(Days::new(amount.into())),
+        "week" => now.checked_sub_days(Days::new(u64::from(amount) * 7)),
+        "month" => now.checked_sub_months(Months::new(amount)),
+        "year" => now.checked_sub_months(Months::new(amount.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+/// The dates of a `Filter::DateRange`, a missing start or end means that side of the range is open.
+///
+/// An end that is only a day (like `2024-01-31`) includes the whole day, so the range ends before the start of the next day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    start: Option<DateTime<FixedOffset>>,
+    end: Bound<DateTime<FixedOffset>>,
+}
+
+impl DateRange {
+    /// Parses the start and end of a date range, an empty start or end leaves that side of the range open.
+    pub fn parse(start: &str, end: &str) -> Result<Self, Error> {
+        let parse = |date: &str| {
+            if date.trim().is_empty() {
+                Ok(None)
+            } else {
+                parse_date(date).map(Some)
+            }
+        };
+        let next_day = end
+            .trim()
+            .parse::<NaiveDate>()
+            .ok()
+            .and_then(|day| day.succ_opt());
+        let range = Self {
+            start: parse(start)?,
+            end: match (next_day, parse(end)?) {
+                (Some(next_day), _) => {
+                    Bound::Excluded(next_day.and_time(NaiveTime::MIN).and_utc().fixed_offset())
+                }
+                (None, Some(end)) => Bound::Included(end),
+                (None, None) => Bound::Unbounded,
+            },
+        };
+        let empty = match (range.start, range.end) {
+            (Some(start), Bound::Included(end)) => start > end,
+            (Some(start), Bound::Excluded(end)) => start >= end,
+            _ => false,
+        };
+        if empty {
+            return Err(Error::InvalidDateRange(start.to_string(), end.to_string()));
+        }
+        Ok(range)
+    }
+
+    pub fn contains(&self, date: DateTime<FixedOffset>, semantics: DateSemantics) -> bool {
+        let date = semantics.key(date);
+        self.start.is_none_or(|start| semantics.key(start) <= date)
+            && match self.end {
+                Bound::Included(end) => date <= semantics.key(end),
+                Bound::Excluded(end) => date < semantics.key(end),
+                Bound::Unbounded => true,
+            }
+    }
+}
@@ -0,0 +1,112 @@
+use std::{error, fmt};
+
+use function_grep::supported_languages::InstantiationError;
+
+/// The errors that searching (or filtering) the history of a function can give back.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// There is no git repository at the given path (or the current directory), or it could not be opened.
+    RepositoryNotFound(Box<gix::discover::Error>),
+    /// The revision to search could not be resolved to any commits.
+    InvalidRevision(String, Box<dyn error::Error + Send + Sync>),
+    /// A date of a filter could not be parsed.
+    InvalidDate(String, chrono::ParseError),
+    /// The start of a date range is after its end.
+    InvalidDateRange(String, String),
+    /// The file to search is not a file of any of the languages we search in, with a description of the supported languages.
+    UnsupportedFile(String, String),
+    /// The filter (or option) cannot be used here, with why not.
+    InvalidFilter(String),
+    /// The name of the function to search for is empty.
+    EmptyName,
+    /// The function was not found in any commit, or nothing was left after filtering.
+    NoHistory,
+    /// The search was cancelled with `SearchOptions::cancel`.
+    Cancelled,
+    /// No commit in the history has a hash that starts with the given hash.
+    CommitNotFound(String),
+    /// More than one commit in the history has a hash that starts with the given hash.
+    AmbiguousCommit(String),
+    /// Reading something from the repository failed, with what we were trying to read.
+    Git(String, Box<dyn error::Error + Send + Sync>),
+    /// One of the languages could not search for the function name.
+    Language(InstantiationError),
+    /// Searching a file for the function failed.
+    FunctionGrep(function_grep::Error),
+}
+
+impl Error {
+    /// Wraps an error from the repository, `context` says what we were trying to read.
+    pub(crate) fn git<E: error::Error + Send + Sync + 'static>(
+        context: &str,
+    ) -> impl FnOnce(E) -> Self + '_ {
+        move |e| Self::Git(context.to_string(), Box::new(e))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RepositoryNotFound(e) => write!(f, "could not find git repository: {e}"),
+            Self::InvalidRevision(revision, e) => write!(f, "invalid revision {revision}: {e}"),
+            Self::InvalidDate(date, e) => write!(f, "invalid date {date}: {e}"),
+            Self::InvalidDateRange(start, end) => {
+                write!(f, "start date {start} is after end date {end}")
+            }
+            Self::UnsupportedFile(file, supported) => write!(
+                f,
+                "file {file} is not a supported file, the following files are supported {supported}"
+            ),
+            Self::InvalidFilter(why) => write!(f, "invalid filter: {why}"),
+            Self::EmptyName => write!(f, "function name is empty"),
+            Self::NoHistory => write!(f, "no history found"),
+            Self::Cancelled => write!(f, "search was cancelled"),
+            Self::CommitNotFound(hash) => write!(f, "no commit found starting with {hash}"),
+            Self::AmbiguousCommit(hash) => {
+                write!(f, "more than one commit starts with {hash}")
+            }
+            Self::Git(context, e) => write!(f, "{context}: {e}"),
+            Self::Language(e) => write!(f, "could not search for function: {e}"),
+            Self::FunctionGrep(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::RepositoryNotFound(e) => Some(e),
+            Self::InvalidRevision(_, e) | Self::Git(_, e) => Some(&**e),
+            Self::InvalidDate(_, e) => Some(e),
+            Self::Language(e) => Some(e),
+            Self::FunctionGrep(e) => Some(e),
+            Self::InvalidDateRange(..)
+            | Self::UnsupportedFile(..)
+            | Self::InvalidFilter(_)
+            | Self::EmptyName
+            | Self::NoHistory
+            | Self::Cancelled
+            | Self::CommitNotFound(_)
+            | Self::AmbiguousCommit(_) => None,
+        }
+    }
+}
+
+impl From<gix::discover::Error> for Error {
+    fn from(e: gix::discover::Error) -> Self {
+        Self::RepositoryNotFound(Box::new(e))
+    }
+}
+
+impl From<InstantiationError> for Error {
+    fn from(e: InstantiationError) -> Self {
+        Self::Language(e)
+    }
+}
+
+impl From<function_grep::Error> for Error {
+    fn from(e: function_grep::Error) -> Self {
+        Self::FunctionGrep(e)
+    }
+}
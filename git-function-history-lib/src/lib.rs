@@ -15,53 +15,63 @@
 ///
 /// Different types that can extracted from the result of `get_function_history`.
 pub mod types;
-macro_rules! get_item_from {
-    ($oid:expr, $repo:expr, $typs:ident) => {
-        gix::hash::ObjectId::from($oid)
-            .attach(&$repo)
-            .object()
-            .map_err(|_| "Could not find object")?
-            .$typs()
-            .map_err(|_| format!("Could not find {} from object", stringify!($typs)))?
-    };
-}
 
-macro_rules! get_item_from_oid_option {
-    ($oid:expr, $repo:expr, $typs:ident) => {
-        gix::hash::ObjectId::from($oid.id)
-            .attach(&$repo)
-            .object()
-            .ok()?
-            .$typs()
-            .ok()
-    };
-}
-use chrono::{DateTime, Utc};
+mod cache;
+mod compiled;
+mod dates;
+mod error;
+mod parallelism;
+mod paths;
+
+use chrono::{DateTime, FixedOffset};
 use function_grep::{
+    find_named_nodes_of_kinds, get_file_type_from_file,
     supported_languages::{InstantiateMap, InstantiatedLanguage, SupportedLanguage},
     ParsedFile,
 };
 use git_function_history_proc_macro::enumstuff;
 
 use log::{info, warn};
-#[cfg(feature = "parallel")]
-use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 
+use cache::{Cache, Found, Stats};
+use compiled::CompiledFilter;
+use dates::DateSemantics;
 use gix::{objs, prelude::ObjectIdExt, ObjectId, Tree};
-use std::{error::Error, ops::Sub};
+use parallelism::Execution;
+use paths::FileMatcher;
+use regex::Regex;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    convert::Infallible,
+    fmt, iter,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
+pub use error::Error;
+#[cfg(feature = "parallel")]
+pub use parallelism::SearchPool;
+pub use parallelism::{ParallelStrategy, Parallelism};
 pub use types::{Commit, FunctionHistory};
 
 /// Different filetypes that can be used to ease the process of finding functions using `get_function_history`.
 /// path separator is `/`.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum FileFilterType {
     /// When you have a absolute path to a file.
     Absolute(String),
-    /// When you have a relative path to a file and or want to find look in all files match a name (aka `ends_with`).
+    /// When you have a relative path to a file and or want to find look in all files match a name (aka `ends_with`, but only for whole path components).
     Relative(String),
-    /// When you want to filter only files in a specific directory
+    /// When you want to filter only files in a specific directory (at any depth, so `app` matches `src/app/main.rs` but not `src/mapper.rs`).
     Directory(String),
+    /// When you want to look in all files that match a glob, like `src/**/handlers/*.rs` (`*` does not match a `/` but `**` does).
+    Glob(String),
+    /// When you want to look in all files whose path matches a regex (anywhere in the path, unless it is anchored with `^` and `$`).
+    Regex(String),
     /// When you don't know the path to a file.
     None,
 }
@@ -73,20 +83,41 @@ pub enum FileFilterType {
 pub enum Filter {
     /// When you want to filter by a commit hash.
     CommitHash(String),
-    /// When you want to filter by a specific date (in rfc2822 format).
+    /// When you want to filter by a specific date.
+    ///
+    /// Dates can be in rfc2822 or ISO 8601 format, or relative to now like `yesterday` or `2 weeks ago`.
     Date(String),
-    /// When you want to filter from one ate to another date (both in rfc2822 format).
+    /// When you want to filter from one date to another date (in the same formats as `Filter::Date`).
+    ///
+    /// An empty start or end date leaves that side of the range open, see `Filter::since` and `Filter::until`.
+    /// An end date that is only a day (like `2024-01-31`) includes the whole day.
+    ///
+    /// The dates of `Filter::Date` and `Filter::DateRange` are when the commits were committed, see `Filter::AuthorDateRange` for when they were authored.
     DateRange(String, String),
+    /// When you want to filter by when the commits were authored, from one date to another date (like `Filter::DateRange`).
+    AuthorDateRange(String, String),
     /// When you have a absolute path to a file.
     FileAbsolute(String),
     /// When you have a relative path to a file and or want to find look in all files match a name.
     FileRelative(String),
     /// When you want to filter only files in a specific directory
     Directory(String),
-    /// when you want to filter by a any commit author name that contains a specific string
+    /// When you want to filter only files that match a glob (see `FileFilterType::Glob`).
+    FileGlob(String),
+    /// When you want to filter only files whose path matches a regex (see `FileFilterType::Regex`).
+    FileRegex(String),
+    /// when you want to filter by a any commit author name that contains a specific string (ignoring case).
+    ///
+    /// Authors are mapped to their real name and email with the `.mailmap` of the repository, so every name they used is found.
     Author(String),
-    /// when you want to filter by a any commit author email that contains a specific string
+    /// when you want to filter by a any commit author email that contains a specific string (ignoring case)
     AuthorEmail(String),
+    /// when you want to filter by a regex that matches the commit author as `Name <email>`
+    AuthorRegex(String),
+    /// when you want to filter by a any committer name (who applied the commit) that contains a specific string (ignoring case)
+    Committer(String),
+    /// when you want to filter by a any committer email that contains a specific string (ignoring case)
+    CommitterEmail(String),
     // when you want to filter by a a commit message that contains a specific string
     Message(String),
     /// when you want to filter by proggramming language filter
@@ -96,9 +127,481 @@ pub enum Filter {
     Language(String),
     /// When you want to filter by nothing.
     None,
+    /// when you want only what matches all of the filters
+    #[enumstuff(skip)]
+    And(Vec<Self>),
+    /// when you want what matches any of the filters
+    #[enumstuff(skip)]
+    Or(Vec<Self>),
+    /// when you want only what does not match the filter
+    #[enumstuff(skip)]
+    Not(Box<Self>),
+    /// when you want the dates of the filter to be compared with the local time of the commits, instead of as points in time (see `Filter::local_time`)
+    #[enumstuff(skip)]
+    LocalTime(Box<Self>),
+}
+
+impl Filter {
+    /// Filters to the commits since `date` (including it).
+    pub fn since(date: impl Into<String>) -> Self {
+        Self::DateRange(date.into(), String::new())
+    }
+
+    /// Filters to the commits until `date` (including it, when `date` is only a day that is the whole day).
+    pub fn until(date: impl Into<String>) -> Self {
+        Self::DateRange(String::new(), date.into())
+    }
+
+    /// Compares the dates of this filter with the local time of the commits (the date and time of day wherever they were made).
+    ///
+    /// So `Filter::since("2024-01-01").local_time()` also keeps a commit made at 2024-01-01 01:00 +02:00,
+    /// which is still 2023 in UTC.
+    #[must_use]
+    pub fn local_time(self) -> Self {
+        Self::LocalTime(Box::new(self))
+    }
+
+    /// Combines this filter with `other`, so that both have to match.
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        match self {
+            Self::And(mut filters) => {
+                filters.push(other);
+                Self::And(filters)
+            }
+            filter => Self::And(vec![filter, other]),
+        }
+    }
+
+    /// Combines this filter with `other`, so that either one has to match.
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        match self {
+            Self::Or(mut filters) => {
+                filters.push(other);
+                Self::Or(filters)
+            }
+            filter => Self::Or(vec![filter, other]),
+        }
+    }
+
+    /// Checks that the filter can be used when searching, which only works for filters on the commit itself,
+    /// and parses its dates (so they are only parsed once for the whole search).
+    ///
+    /// `Filter::Date` picks the closest commit, so it cannot be combined with other filters.
+    fn validate_search(&self, combined: bool) -> Result<CompiledFilter<'_>, Error> {
+        match self {
+            Self::Date(_) if combined => Err(Error::InvalidFilter(
+                "a date filter cannot be combined with other filters when searching".to_string(),
+            )),
+            Self::Date(_)
+            | Self::Author(_)
+            | Self::AuthorEmail(_)
+            | Self::Committer(_)
+            | Self::CommitterEmail(_)
+            | Self::Message(_)
+            | Self::None
+            | Self::CommitHash(_)
+            | Self::DateRange(_, _)
+            | Self::AuthorRegex(_)
+            | Self::AuthorDateRange(_, _) => CompiledFilter::single(self),
+            Self::And(filters) => filters
+                .iter()
+                .map(|filter| filter.validate_search(true))
+                .collect::<Result<_, _>>()
+                .map(CompiledFilter::And),
+            Self::Or(filters) => filters
+                .iter()
+                .map(|filter| filter.validate_search(true))
+                .collect::<Result<_, _>>()
+                .map(CompiledFilter::Or),
+            Self::Not(filter) => Ok(CompiledFilter::Not(Box::new(filter.validate_search(true)?))),
+            Self::LocalTime(filter) => Ok(CompiledFilter::LocalTime(Box::new(
+                filter.validate_search(combined)?,
+            ))),
+            _ => Err(Error::InvalidFilter(format!(
+                "{self:?} cannot be used when searching"
+            ))),
+        }
+    }
+}
+
+impl std::ops::Not for Filter {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self::Not(Box::new(self))
+    }
+}
+
+/// Extra options that change how `get_function_history_with_options` walks the history.
+///
+/// The default options give the same results as `get_function_history`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct SearchOptions {
+    /// Follow the file across renames and moves (like `git log --follow`).
+    /// This only works with `FileFilterType::Absolute`, the path is updated every time the file was renamed in a commit.
+    pub follow: bool,
+    /// Keep following the function when it was renamed.
+    /// When the function cannot be found in a commit anymore, we look for a function with a very similar body (in the same kind of file),
+    /// and keep on searching using its (old) name. The commit where the function got renamed records the old name (see `Commit::renamed_from`).
+    ///
+    /// Note: this makes the search go through the commits one by one.
+    pub follow_function_renames: bool,
+    /// Only keep the commits where the function actually changed.
+    /// Each run of commits with the same function bodies is collapsed into the oldest commit of the run (the one that made the change),
+    /// which records how many newer commits it stands for (see `Commit::unchanged_commits`).
+    pub changes_only: bool,
+    /// The revision to search the history of, instead of `HEAD`.
+    /// This can be anything git understands, like a branch (`feature`), a tag (`v1.0`) or a range (`v1.0..main`, `main...feature`).
+    pub revision: Option<String>,
+    /// The path of the repository to search in (or any directory in it), instead of the one the current directory is in.
+    pub repo: Option<PathBuf>,
+    /// Stops the search once the token gets cancelled (from another thread), the search then returns an error.
+    pub cancel: Option<CancellationToken>,
+    /// Gets told how far along the search is, every time a commit was searched.
+    pub progress: Option<ProgressSink>,
+    /// Glob patterns of files and directories that are not searched, like in a `.gitignore`:
+    /// patterns with a `/` have to match the whole path, others only the name of a file or directory (like `vendor` or `*.generated.rs`).
+    pub exclude: Vec<String>,
+    /// Which commits are searched, depending on whether they are merge commits (see `MergeMode`).
+    pub merges: MergeMode,
+    /// Only keep the commits where the number of matches of the string or regex in the function changed (like `git log -S` or `git log -G`,
+    /// but only looking inside the function), to find the commits that added or removed something from it.
+    ///
+    /// Each commit is compared with the next older commit the function was found in (the oldest one counts as having no matches before it).
+    pub pickaxe: Option<Pickaxe>,
+    /// How the search uses threads (with the `parallel` feature), see `Parallelism`.
+    ///
+    /// By default this depends on how many commits there are to search, use `Parallelism::max_threads` to limit the threads a search uses,
+    /// or `Parallelism::pool` to search on your own thread pool.
+    pub parallelism: Parallelism,
+    /// Keep where the function was found in each file on disk between searches (with the `cache` feature),
+    /// so the files that did not change since the last search of the same function do not have to be parsed again.
+    ///
+    /// The cache is written into the git directory (in `function-history-cache`), so this is off by default.
+    pub disk_cache: bool,
+}
+
+/// What to look for in the function bodies, see `SearchOptions::pickaxe`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pickaxe {
+    /// Look for the string as it is.
+    Literal(String),
+    /// Look for matches of the regex.
+    Regex(String),
+}
+
+impl Pickaxe {
+    fn compile(&self) -> Result<Regex, Error> {
+        match self {
+            Self::Literal(literal) => paths::compile_regex(&regex::escape(literal)),
+            Self::Regex(regex) => paths::compile_regex(regex),
+        }
+    }
+}
+
+/// How merge commits are handled when walking the history, see `SearchOptions::merges`.
+///
+/// A function changed on a branch is found in the commit on the branch and again in the merge commit,
+/// these modes let you see only one of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeMode {
+    /// Search all commits.
+    #[default]
+    All,
+    /// Only follow the first parent of merge commits (like `git log --first-parent`),
+    /// so the changes made on a branch show up as the merge commit that brought them in.
+    FirstParent,
+    /// Search all commits except for merge commits (like `git log --no-merges`).
+    SkipMerges,
+    /// Only search merge commits (like `git log --merges`).
+    MergesOnly,
+}
+
+impl MergeMode {
+    /// checks if a commit with `parents` parents should be searched
+    const fn wanted(self, parents: usize) -> bool {
+        match self {
+            Self::All | Self::FirstParent => true,
+            Self::SkipMerges => parents <= 1,
+            Self::MergesOnly => parents > 1,
+        }
+    }
+}
+
+impl SearchOptions {
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+/// A token to cancel a running search with, see `SearchOptions::cancel`.
+///
+/// All clones of a token share the same state, so a search can be cancelled using a clone of the token it was given.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all the searches using this token (or a clone of it).
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Tokens are equal when they are clones of each other.
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+/// How far along a search is, see `SearchOptions::progress`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// How many commits were searched so far.
+    pub commits_walked: usize,
+    /// How many commits will be searched in total,
+    /// while the commits are still being walked (see `total_known`) only the ones walked so far are counted.
+    pub total_commits: usize,
+    /// Whether all the commits to search were walked, so `total_commits` will not grow anymore.
+    pub total_known: bool,
+    /// How many blobs had to be parsed so far.
+    pub blobs_parsed: usize,
+    /// How many times a blob or tree did not have to be searched (again), as it was already in the cache.
+    pub cache_hits: usize,
+}
+
+impl Progress {
+    /// How much of the search is done (from 0 to 1), or `None` if it is not known yet how many commits there are to search.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fraction(&self) -> Option<f32> {
+        if !self.total_known {
+            None
+        } else if self.total_commits == 0 {
+            Some(0.0)
+        } else {
+            Some(self.commits_walked as f32 / self.total_commits as f32)
+        }
+    }
+
+    /// Whether every commit was searched.
+    pub const fn done(&self) -> bool {
+        self.total_known && self.commits_walked == self.total_commits
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}{} commits, {} blobs parsed, {} cache hits",
+            self.commits_walked,
+            self.total_commits,
+            if self.total_known { "" } else { "+" },
+            self.blobs_parsed,
+            self.cache_hits
+        )
+    }
+}
+
+/// Receives the progress of a search, see `SearchOptions::progress`.
+///
+/// Commits can be searched in parallel, so the progress can be reported from any thread.
+#[derive(Clone)]
+pub struct ProgressSink(Arc<dyn Fn(Progress) + Send + Sync>);
+
+impl ProgressSink {
+    pub fn new(report: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(report))
+    }
+
+    fn report(&self, progress: Progress) {
+        (self.0)(progress);
+    }
+}
+
+impl fmt::Debug for ProgressSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressSink")
+    }
+}
+
+/// Sinks are equal when they are clones of each other.
+impl PartialEq for ProgressSink {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ProgressSink {}
+
+/// Counts the walked and searched commits, and reports the progress to `SearchOptions::progress` (if given).
+struct ProgressReporter<'a> {
+    sink: Option<&'a ProgressSink>,
+    total_commits: AtomicUsize,
+    total_known: AtomicBool,
+    commits_walked: AtomicUsize,
+    stats: Arc<Stats>,
+}
+
+impl<'a> ProgressReporter<'a> {
+    const fn new(options: &'a SearchOptions, stats: Arc<Stats>) -> Self {
+        Self {
+            sink: options.progress.as_ref(),
+            total_commits: AtomicUsize::new(0),
+            total_known: AtomicBool::new(false),
+            commits_walked: AtomicUsize::new(0),
+            stats,
+        }
+    }
+
+    /// Counts the `commits` (that are going to be searched) as they are walked.
+    fn walking<'s, T>(
+        &'s self,
+        commits: impl Iterator<Item = T> + 's,
+    ) -> impl Iterator<Item = T> + 's {
+        let mut commits = commits.peekable();
+        iter::from_fn(move || {
+            let commit = commits.next()?;
+            self.total_commits.fetch_add(1, Ordering::Relaxed);
+            // we look one commit ahead, so the total is known before the last commit is searched
+            if commits.peek().is_none() {
+                self.total_known.store(true, Ordering::Relaxed);
+            }
+            Some(commit)
+        })
+    }
+
+    fn commit_walked(&self) {
+        let commits_walked = self.commits_walked.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(sink) = self.sink {
+            sink.report(Progress {
+                commits_walked,
+                total_commits: self.total_commits.load(Ordering::Relaxed),
+                total_known: self.total_known.load(Ordering::Relaxed),
+                blobs_parsed: self.stats.parsed_blobs(),
+                cache_hits: self.stats.hits(),
+            });
+        }
+    }
+}
+
+/// How similar (from 0 to 1) a function body has to be to the last found version of the function, to be counted as the function before it was renamed.
+const RENAME_SIMILARITY: f64 = 0.8;
+
+/// the name and email (after applying the `.mailmap`) of the author or committer of a commit, and when they authored or committed it
+struct Signature {
+    name: String,
+    email: String,
+    time: DateTime<FixedOffset>,
+}
+
+impl Signature {
+    fn read(
+        signature: gix::actor::SignatureRef<'_>,
+        mailmap: &gix::mailmap::Snapshot,
+    ) -> Result<Self, Error> {
+        let time = signature
+            .time()
+            .map_err(Error::git("could not read the date of a commit"))?;
+        // keep the timezone of whoever made the commit, so the dates are shown in their local time
+        let time = FixedOffset::east_opt(time.offset)
+            .zip(DateTime::from_timestamp(time.seconds, 0))
+            .map(|(offset, time)| time.with_timezone(&offset))
+            .ok_or_else(|| {
+                Error::Git(
+                    "could not read the date of a commit".to_string(),
+                    "the date is out of range".into(),
+                )
+            })?;
+        let resolved = mailmap.resolve(signature);
+        Ok(Self {
+            name: resolved.name.to_string(),
+            email: resolved.email.to_string(),
+            time,
+        })
+    }
+}
+
+/// what the commits get filtered by before they are searched
+struct CommitMetadata {
+    message: String,
+    hash: String,
+    parents: Vec<String>,
+    author: Signature,
+    committer: Signature,
+}
+
+impl CommitMetadata {
+    fn read(commit: &gix::Commit<'_>, mailmap: &gix::mailmap::Snapshot) -> Result<Self, Error> {
+        let messages = commit
+            .message()
+            .map_err(Error::git("could not read the message of a commit"))?;
+        let mut message = messages.title.to_string();
+        if let Some(body) = messages.body {
+            message.push_str(&body.to_string());
+        }
+        Ok(Self {
+            message,
+            hash: commit.id().to_hex().to_string(),
+            parents: commit
+                .parent_ids()
+                .map(|id| id.to_hex().to_string())
+                .collect(),
+            author: Signature::read(
+                commit
+                    .author()
+                    .map_err(Error::git("could not read the author of a commit"))?,
+                mailmap,
+            )?,
+            committer: Signature::read(
+                commit
+                    .committer()
+                    .map_err(Error::git("could not read the committer of a commit"))?,
+                mailmap,
+            )?,
+        })
+    }
+
+    /// the commit with the `files` the function was found in
+    fn to_commit(&self, files: Vec<ParsedFile>) -> Commit {
+        Commit::new_at(
+            &self.hash,
+            files,
+            self.committer.time,
+            &self.author.name,
+            &self.author.email,
+            &self.message,
+        )
+        .with_committer(
+            self.author.time,
+            &self.committer.name,
+            &self.committer.email,
+        )
+        .with_parents(self.parents.clone())
+    }
 }
 
-/// Valid filters are: `Filter::CommitId`, `Filter::Date`, `Filter::DateRange`.
+/// Valid filters are the ones on the commit itself:
+///
+/// `Filter::CommitId`, `Filter::Date`, `Filter::DateRange`, `Filter::AuthorDateRange`,
+/// `Filter::Author`, `Filter::AuthorEmail`, `Filter::AuthorRegex`, `Filter::Committer`, `Filter::CommitterEmail`, `Filter::Message`.
+///
+/// They can be combined with `Filter::And`, `Filter::Or` and `Filter::Not` of them (except for `Filter::Date`).
 ///
 /// Checks if git is installed if its not it will error out with `git is not installed`.
 /// <br>
@@ -133,41 +636,375 @@ pub enum Filter {
 /// If no files were found that match the criteria given, this will return an 'Err'
 /// Or if it cannot find or read from a git repository
 ///
-// TODO: split this function into smaller functions
 // TODO: allow more complex language type filters.
-// TODO: allow multiple filters.
 pub fn get_function_history(
     name: &str,
     file: &FileFilterType,
     filter: &Filter,
     langs: &[&dyn SupportedLanguage],
-) -> Result<FunctionHistory, Box<dyn Error + Send + Sync>> {
+) -> Result<FunctionHistory, Error> {
+    get_function_history_with_options(name, file, filter, langs, &SearchOptions::default())
+}
+
+/// Same as `get_function_history`, but lets you change how the history is walked with `SearchOptions`.
+///
+/// # examples
+///
+/// ```
+/// use git_function_history::{get_function_history_with_options, Filter, FileFilterType, SearchOptions};
+/// let t = get_function_history_with_options(
+///     "empty_test",
+///     &FileFilterType::Absolute("git-function-history-lib/src/test_functions.rs".to_string()),
+///     &Filter::None,
+///     function_grep::supported_languages::predefined_languages(),
+///     &SearchOptions {
+///         follow: true,
+///         ..SearchOptions::default()
+///     },
+/// );
+/// ```
+///
+/// # Errors
+///
+/// If no files were found that match the criteria given, this will return an 'Err'
+/// Or if it cannot find or read from a git repository
+/// Or if `SearchOptions::follow` is used without a `FileFilterType::Absolute`
+pub fn get_function_history_with_options(
+    name: &str,
+    file: &FileFilterType,
+    filter: &Filter,
+    langs: &[&dyn SupportedLanguage],
+    options: &SearchOptions,
+) -> Result<FunctionHistory, Error> {
+    let repo = discover_repo(options)?;
+    get_function_history_in(&repo, name, file, filter, langs, options)
+}
+
+/// Same as `get_function_history_with_options`, but searches in an already opened repository (`SearchOptions::repo` is ignored).
+///
+/// # examples
+///
+/// ```
+/// use git_function_history::{get_function_history_in, Filter, FileFilterType, SearchOptions};
+/// let repo = gix::discover(".").unwrap();
+/// let t = get_function_history_in(
+///     &repo,
+///     "empty_test",
+///     &FileFilterType::Relative("src/test_functions.rs".to_string()),
+///     &Filter::None,
+///     function_grep::supported_languages::predefined_languages(),
+///     &SearchOptions::default(),
+/// );
+/// ```
+///
+/// # Errors
+///
+/// If no files were found that match the criteria given, this will return an 'Err'
+/// Or if it cannot read from the git repository
+/// Or if `SearchOptions::follow` is used without a `FileFilterType::Absolute`
+/// Or if the search was cancelled
+pub fn get_function_history_in(
+    repo: &gix::Repository,
+    name: &str,
+    file: &FileFilterType,
+    filter: &Filter,
+    langs: &[&dyn SupportedLanguage],
+    options: &SearchOptions,
+) -> Result<FunctionHistory, Error> {
+    let mut commits = vec![];
+    stream_function_history_in(repo, name, file, filter, langs, options, |commit| {
+        commits.push(commit);
+    })?;
+    Ok(FunctionHistory::new(name.to_string(), commits))
+}
+
+/// Same as `get_function_history_with_options`, but passes each commit to `found` as soon as it is found (newest first).
+///
+/// This way results can be shown while the search is still running, use `SearchOptions::cancel` to stop a long search early.
+///
+/// # examples
+///
+/// ```
+/// use git_function_history::{stream_function_history, Filter, FileFilterType, SearchOptions};
+/// let t = stream_function_history(
+///     "empty_test",
+///     &FileFilterType::Relative("src/test_functions.rs".to_string()),
+///     &Filter::None,
+///     function_grep::supported_languages::predefined_languages(),
+///     &SearchOptions::default(),
+///     |commit| println!("found in {}", commit.get_metadata()["commit hash"]),
+/// );
+/// ```
+///
+/// # Errors
+///
+/// Same as `get_function_history_with_options`, the commits that were already passed to `found` are still valid
+pub fn stream_function_history(
+    name: &str,
+    file: &FileFilterType,
+    filter: &Filter,
+    langs: &[&dyn SupportedLanguage],
+    options: &SearchOptions,
+    found: impl FnMut(Commit),
+) -> Result<(), Error> {
+    let repo = discover_repo(options)?;
+    stream_function_history_in(&repo, name, file, filter, langs, options, found)
+}
+
+/// Same as `stream_function_history`, but searches in an already opened repository (`SearchOptions::repo` is ignored).
+///
+/// # Errors
+///
+/// Same as `get_function_history_in`, the commits that were already passed to `found` are still valid
+///
+// TODO: split this function into smaller functions
+pub fn stream_function_history_in(
+    repo: &gix::Repository,
+    name: &str,
+    file: &FileFilterType,
+    filter: &Filter,
+    langs: &[&dyn SupportedLanguage],
+    options: &SearchOptions,
+    found: impl FnMut(Commit),
+) -> Result<(), Error> {
     // chack if name is empty
     if name.is_empty() {
-        Err("function name is empty")?;
+        return Err(Error::EmptyName);
     }
     // if filter is date list all the dates and find the one that is closest to the date set that to closest_date and when using the first filter check if the date of the commit is equal to the closest_date
     // find the closest date by using get_git_dates_commits_oxide
-    match filter {
-        Filter::Date(date) => {
-            DateTime::parse_from_rfc2822(date)?;
-        }
-        Filter::Author(_)
-        | Filter::AuthorEmail(_)
-        | Filter::Message(_)
-        | Filter::None
-        | Filter::CommitHash(_) => (),
-        Filter::DateRange(start, end) => {
-            // check if start is before end
-            // vaildate that the dates are valid
-            let start = DateTime::parse_from_rfc2822(start)?.with_timezone(&Utc);
-            let end = DateTime::parse_from_rfc2822(end)?.with_timezone(&Utc);
-            if start > end {
-                Err("start date is after end date")?;
+    let filter = &filter.validate_search(false)?;
+    let followed_path = check_file(file, langs, options)?;
+    let matcher = &FileMatcher::new(langs, file, options)?;
+    let th_repo = Arc::new(repo.clone().into_sync());
+    let mut found = Changes::new(found, options)?;
+    let failed = &RefCell::new(None);
+    let commits = until_failed(
+        walk_commits(repo, followed_path.as_deref(), options)?,
+        failed,
+    );
+    if options.follow_function_renames {
+        track_function_renames(
+            name,
+            commits,
+            &th_repo,
+            langs,
+            file,
+            filter,
+            options,
+            &mut |commit| found.push(commit),
+        )?;
+        if let Some(e) = failed.take() {
+            return Err(e);
+        }
+        return found.finish(options);
+    }
+    let langs = langs.instantiate_map(name)?;
+    let langs = langs.as_slice();
+    let stats = Arc::<Stats>::default();
+    let cache = Cache::open(&th_repo, name, stats.clone(), options.disk_cache);
+    let progress = ProgressReporter::new(options, stats);
+    let commits = progress.walking(filter_commits(commits, filter));
+    let (execution, commits) = plan_search(commits, options, &th_repo);
+    let execution = &execution;
+
+    // todo use itertools to split into vec of oks and errs
+    // and report some of errors if no oks and if no oks and errs report no history found
+    let search_commit = |i: &WalkedCommit| {
+        if options.is_cancelled() {
+            return None;
+        }
+        let tree = sender(
+            i.0,
+            &th_repo.to_thread_local(),
+            matcher,
+            langs,
+            &followed_file(file, i.1.as_ref()),
+            &cache,
+            execution,
+        );
+        progress.commit_walked();
+        Some(tree.map(|tree| {
+            if tree.is_empty() {
+                Searched::Missing(i.2.hash.clone())
+            } else {
+                Searched::Found(i.2.to_commit(tree).with_tracked_path(i.1.clone()))
             }
+        }))
+    };
+    execution.search_commits(commits, search_commit, |searched| {
+        if let Some(searched) = passed(searched, failed) {
+            found.searched(searched);
+        }
+    });
+    if let Some(e) = failed.take() {
+        return Err(e);
+    }
+    found.finish(options)
+}
+
+/// Same as `get_function_history_with_options`, but searches for all the functions in `names` in one go.
+///
+/// The history is only walked once, and each blob is only parsed once for all the names,
+/// which is a lot faster than searching for each name on its own.
+/// Returns the history of each name, names that no history was found for are left out.
+///
+/// # examples
+///
+/// ```
+/// use git_function_history::{get_function_histories, Filter, FileFilterType, SearchOptions};
+/// let histories = get_function_histories(
+///     &["empty_test", "not_empty_test"],
+///     &FileFilterType::Relative("src/test_functions.rs".to_string()),
+///     &Filter::None,
+///     function_grep::supported_languages::predefined_languages(),
+///     &SearchOptions::default(),
+/// );
+/// ```
+///
+/// # Errors
+///
+/// Same as `get_function_history_with_options`, but not finding any history for a name is not an error
+/// Or if `SearchOptions::follow_function_renames` is used, as each function could be renamed in a different commit
+pub fn get_function_histories(
+    names: &[&str],
+    file: &FileFilterType,
+    filter: &Filter,
+    langs: &[&dyn SupportedLanguage],
+    options: &SearchOptions,
+) -> Result<HashMap<String, FunctionHistory>, Error> {
+    let repo = discover_repo(options)?;
+    get_function_histories_in(&repo, names, file, filter, langs, options)
+}
+
+/// Same as `get_function_histories`, but searches in an already opened repository (`SearchOptions::repo` is ignored).
+///
+/// # Errors
+///
+/// Same as `get_function_histories`
+pub fn get_function_histories_in(
+    repo: &gix::Repository,
+    names: &[&str],
+    file: &FileFilterType,
+    filter: &Filter,
+    langs: &[&dyn SupportedLanguage],
+    options: &SearchOptions,
+) -> Result<HashMap<String, FunctionHistory>, Error> {
+    if names.iter().any(|name| name.is_empty()) {
+        return Err(Error::EmptyName);
+    }
+    if options.follow_function_renames {
+        return Err(Error::InvalidFilter(
+            "following function renames only works when searching for one function".to_string(),
+        ));
+    }
+    let filter = &filter.validate_search(false)?;
+    let mut names = names.to_vec();
+    names.sort_unstable();
+    names.dedup();
+    let followed_path = check_file(file, langs, options)?;
+    let matcher = &FileMatcher::new(langs, file, options)?;
+    let th_repo = Arc::new(repo.clone().into_sync());
+    let mut histories = vec![vec![]; names.len()];
+    let mut found = histories
+        .iter_mut()
+        .map(|commits| Changes::new(|commit| commits.push(commit), options))
+        .collect::<Result<Vec<_>, _>>()?;
+    let failed = &RefCell::new(None);
+    let commits = until_failed(
+        walk_commits(repo, followed_path.as_deref(), options)?,
+        failed,
+    );
+    let langs = names
+        .iter()
+        .map(|name| langs.instantiate_map(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    let langs = langs.as_slice();
+    let stats = Arc::<Stats>::default();
+    let caches = names
+        .iter()
+        .map(|name| Cache::open(&th_repo, name, stats.clone(), options.disk_cache))
+        .collect::<Vec<_>>();
+    let caches = caches.as_slice();
+    let progress = ProgressReporter::new(options, stats);
+    let commits = progress.walking(filter_commits(commits, filter));
+    let (execution, commits) = plan_search(commits, options, &th_repo);
+    let execution = &execution;
+
+    // what was found for each name in the commit
+    let search_commit = |i: &WalkedCommit| {
+        if options.is_cancelled() {
+            return None;
+        }
+        let repo = th_repo.to_thread_local();
+        let trees = find_tree(i.0, &repo).and_then(|tree| {
+            search_tree_many(
+                &tree,
+                &repo,
+                "",
+                matcher,
+                langs,
+                &followed_file(file, i.1.as_ref()),
+                caches,
+                execution,
+            )
+        });
+        progress.commit_walked();
+        let searched = trees.map(|trees| {
+            trees
+                .into_iter()
+                .map(|tree| {
+                    if tree.is_empty() {
+                        Searched::Missing(i.2.hash.clone())
+                    } else {
+                        Searched::Found(i.2.to_commit(tree).with_tracked_path(i.1.clone()))
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+        Some(searched)
+    };
+    execution.search_commits(commits, search_commit, |searched| {
+        let Some(searched) = passed(searched, failed) else {
+            return;
+        };
+        for (found, searched) in found.iter_mut().zip(searched) {
+            found.searched(searched);
         }
-        _ => Err("invalid filter")?,
+    });
+    if let Some(e) = failed.take() {
+        return Err(e);
     }
+    // whether any history was found for each name
+    let any_found = found
+        .into_iter()
+        .map(|found| match found.finish(options) {
+            Ok(()) => Ok(true),
+            Err(Error::NoHistory) => Ok(false),
+            Err(e) => Err(e),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(names
+        .into_iter()
+        .zip(histories)
+        .zip(any_found)
+        .filter(|(_, any_found)| *any_found)
+        .map(|((name, commits), _)| {
+            (
+                name.to_string(),
+                FunctionHistory::new(name.to_string(), commits),
+            )
+        })
+        .collect())
+}
+
+/// Checks that the file filter can be searched with the languages,
+/// and returns the path to follow (when following renames with `SearchOptions::follow`).
+fn check_file(
+    file: &FileFilterType,
+    langs: &[&dyn SupportedLanguage],
+    options: &SearchOptions,
+) -> Result<Option<String>, Error> {
     match file {
         FileFilterType::Absolute(file) | FileFilterType::Relative(file) => {
             // vaildate that the file makes sense with language
@@ -177,158 +1014,852 @@ pub fn get_function_history(
                 .copied()
                 .any(|i| ends_with_cmp_no_case(file, i));
             if !is_supported {
-                Err(format!(
-                    "file {file} is not a supported file, the following files are supported {}",
+                return Err(Error::UnsupportedFile(
+                    file.clone(),
                     langs
                         .iter()
-                        .map(|lang| format!(
-                            "({} with extension(s) [{}])",
-                            lang.language_name(),
-                            &lang.file_exts().join(",")
-                        ))
+                        .map(|lang| {
+                            format!(
+                                "({} with extension(s) [{}])",
+                                lang.language_name(),
+                                &lang.file_exts().join(",")
+                            )
+                        })
                         .collect::<Vec<_>>()
-                        .join(" ")
-                ))?;
+                        .join(" "),
+                ));
             }
         }
-        FileFilterType::Directory(_) | FileFilterType::None => {}
+        FileFilterType::Directory(_)
+        | FileFilterType::Glob(_)
+        | FileFilterType::Regex(_)
+        | FileFilterType::None => {}
     }
-
-    let langs1 = &*langs
-        .iter()
-        .flat_map(|l| l.file_exts())
-        .copied()
-        .collect::<Box<[_]>>();
-    let repo = gix::discover(".")?;
-    let th_repo = repo.clone().into_sync();
-    let commit_iter = repo.rev_walk(repo.head_id().map(gix::Id::detach)).sorting(
+    Ok(match file {
+        FileFilterType::Absolute(path) if options.follow => Some(path.clone()),
+        _ if options.follow => {
+            return Err(Error::InvalidFilter(
+                "following renames only works with an absolute file path".to_string(),
+            ))
+        }
+        _ => None,
+    })
+}
+
+/// A walked commit: the id of its tree, the path of the followed file in it (see `walk_commits`) and its metadata.
+type WalkedCommit = (ObjectId, Option<String>, CommitMetadata);
+
+/// Walks the commits that have to be searched (newest first), with the path of the file at each commit
+/// (when following `followed_path`, the path it has at the tips) and their metadata.
+///
+/// The commits are only walked (and their metadata read) as the returned iterator is used,
+/// a commit that cannot be read is given back as an error (see `until_failed`).
+fn walk_commits<'a>(
+    repo: &'a gix::Repository,
+    followed_path: Option<&'a str>,
+    options: &'a SearchOptions,
+) -> Result<impl Iterator<Item = Result<WalkedCommit, Error>> + 'a, Error> {
+    let (tips, hidden) = revision_tips(repo, options.revision.as_deref())?;
+    let commit_iter = repo.rev_walk(tips).with_hidden(hidden).sorting(
         gix::revision::walk::Sorting::ByCommitTime(
             gix::traverse::commit::simple::CommitTimeOrder::NewestFirst,
         ),
     );
-    let commit_iter = commit_iter.all()?.filter_map(|id| Some(id.ok()?.detach()));
-    #[cfg(feature = "parallel")]
-    let commit_iter = {
-        // we have to collect here because we don't want any refrences to not send/sync structs
-        let binding = commit_iter.collect::<Vec<_>>();
-        binding.into_par_iter()
+    let commit_iter = if options.merges == MergeMode::FirstParent {
+        commit_iter.first_parent_only()
+    } else {
+        commit_iter
     };
-    let commits = commit_iter.filter_map(|id| {
-        let repo = th_repo.to_thread_local();
-        let commit = id.id.attach(&repo).object().ok()?.try_into_commit().ok()?;
-        let tree = commit.tree().ok()?.id;
-        let time = commit.time().ok()?;
-        let time = DateTime::from_timestamp(time.seconds, 0)?;
-        let authorinfo = commit.author().ok()?;
-        let author = authorinfo.name.to_string();
-        let email = authorinfo.email.to_string();
-        let messages = commit.message().ok()?;
-        let mut message = messages.title.to_string();
-        if let Some(i) = messages.body {
-            message.push_str(i.to_string().as_str());
+    let commit_iter = commit_iter
+        .all()
+        .map_err(Error::git("could not walk the commits"))?
+        .map(|info| {
+            info.map(gix::revision::walk::Info::detach)
+                .map_err(Error::git("could not walk the commits"))
+        })
+        .take_while(|_| !options.is_cancelled());
+    // the path of the file in the commits we did not get to yet, as each commit has the path its (newer) children had it at,
+    // or the one they renamed it from, so that commits on a branch get the path the file had on that branch
+    let mut paths = HashMap::new();
+    let mailmap = repo.open_mailmap();
+    // this has to happen before we (possibly) go parallel, as each commit needs to know about the renames in all the newer commits
+    // (even the ones that are not searched because of `SearchOptions::merges`)
+    Ok(commit_iter.filter_map(move |info| {
+        let info = match info {
+            Ok(info) => info,
+            Err(e) => return Some(Err(e)),
+        };
+        let path = followed_path
+            .as_ref()
+            .map(|tip| follow_path(&info, tip, &mut paths, options.merges, repo));
+        if !options.merges.wanted(info.parent_ids.len()) {
+            return None;
         }
-        let commit = commit.id().to_hex().to_string();
-        let metadata = (message, commit, author, email, time);
-        Some((tree, metadata))
-    });
-    let langs = langs.instantiate_map(name).unwrap();
-    let langs = langs.as_slice();
-    if let Filter::Date(date) = filter {
-        let date = DateTime::parse_from_rfc2822(date)?.with_timezone(&Utc);
-        let commit = commits.min_by_key(|commit| commit.1 .4.sub(date).num_seconds().abs());
-        return if let Some(i) = commit {
-            let tree = sender(i.0, &th_repo.to_thread_local(), langs1, langs, file)?;
+        let read = || {
+            let commit = info
+                .id
+                .attach(repo)
+                .object()
+                .map_err(Error::git("could not find a commit"))?
+                .try_into_commit()
+                .map_err(Error::git("could not read a commit"))?;
+            let tree = commit
+                .tree_id()
+                .map_err(Error::git("could not read the tree of a commit"))?
+                .detach();
+            Ok((tree, path, CommitMetadata::read(&commit, &mailmap)?))
+        };
+        Some(read())
+    }))
+}
 
-            if tree.is_empty() {
-                Err("empty commit found")?;
-            }
+/// Passes on the walked commits until one of them (or the search of an earlier one, see `passed`) failed,
+/// the first error is kept in `failed` to be given back once the search stops.
+fn until_failed<'a, T: 'a>(
+    commits: impl Iterator<Item = Result<T, Error>> + 'a,
+    failed: &'a RefCell<Option<Error>>,
+) -> impl Iterator<Item = T> + 'a {
+    commits
+        .take_while(|_| failed.borrow().is_none())
+        .map_while(|commit| commit.map_err(|e| failed.replace(Some(e))).ok())
+}
 
-            Ok(FunctionHistory::new(
-                name.to_owned(),
-                vec![Commit::new(
-                    &i.1 .1,
-                    tree,
-                    &i.1 .4.to_rfc2822(),
-                    &i.1 .2,
-                    &i.1 .3,
-                    &i.1 .0,
-                )?],
-            ))
-        } else {
-            Err("no history found")?
-        };
-    }
-    let commits = commits.filter(|(_, metadata)| {
-            match filter {
-                Filter::CommitHash(hash) => *hash == metadata.1,
-                Filter::Date(_) => unreachable!(),
-                Filter::DateRange(start, end) => {
-                    // let date = metadata.4.seconds_since_unix_epoch;
-                    let date = metadata.4;
-                    let start = DateTime::parse_from_rfc2822(start)
-                        .map(|i| i.with_timezone(&Utc))
-                        .expect("failed to parse start date, edge case shouldn't happen please file a bug to https://github.com/mendelsshop/git_function_history/issues");
-                    let end = DateTime::parse_from_rfc2822(end)
-                        .map(|i| i.with_timezone(&Utc))
-                        .expect("failed to parse end date, edge case shouldn't happen please file a bug to https://github.com/mendelsshop/git_function_history/issues");
-                    start <= date && date <= end
+/// Gives back what was searched in a commit, unless this (or an earlier) search failed,
+/// the first error is kept in `failed`.
+fn passed<T>(searched: Result<T, Error>, failed: &RefCell<Option<Error>>) -> Option<T> {
+    let mut failed = failed.borrow_mut();
+    match searched {
+        Ok(searched) if failed.is_none() => Some(searched),
+        Ok(_) => None,
+        Err(e) => {
+            failed.get_or_insert(e);
+            None
+        }
+    }
+}
+
+/// Keeps the walked commits that match the filter (as they are walked),
+/// or with `Filter::Date` only the one closest to the date, which needs the whole walk first.
+fn filter_commits<'a>(
+    commits: impl Iterator<Item = WalkedCommit> + 'a,
+    filter: &'a CompiledFilter<'_>,
+) -> Box<dyn Iterator<Item = WalkedCommit> + 'a> {
+    match closest_date(filter) {
+        Some((date, semantics)) => Box::new(
+            commits
+                .min_by_key(|commit| semantics.distance(commit.2.committer.time, date))
+                .into_iter(),
+        ),
+        None => Box::new(
+            commits.filter(|(_, _, metadata)| matches_filter(filter, metadata, DateSemantics::Utc)),
+        ),
+    }
+}
+
+/// Decides how to search the commits from the first few of them (see `Execution::lookahead`),
+/// and returns all of the commits again.
+fn plan_search<'a, T>(
+    mut commits: impl Iterator<Item = T>,
+    options: &'a SearchOptions,
+    repo: &'a gix::ThreadSafeRepository,
+) -> (Execution<'a>, impl Iterator<Item = T>) {
+    let ahead = commits
+        .by_ref()
+        .take(Execution::lookahead(&options.parallelism))
+        .collect::<Vec<_>>();
+    let execution = Execution::new(&options.parallelism, ahead.len(), repo);
+    (execution, ahead.into_iter().chain(commits))
+}
+
+/// What was found when searching a commit.
+#[allow(clippy::large_enum_variant)]
+enum Searched {
+    Found(Commit),
+    /// the function is not in the commit with this hash
+    Missing(String),
+}
+
+/// Passes the found commits (newest first) on to the caller.
+/// With `SearchOptions::changes_only` each run of commits where the function did not change is collapsed into the oldest commit of the run,
+/// so a commit is only passed on once the next change (or the end of the history) is found.
+///
+/// With `SearchOptions::pickaxe` a (collapsed) commit is only passed on if it has a different number of matches than the next older one.
+struct Changes<F> {
+    found: F,
+    collapse_unchanged: bool,
+    // the commit that stands for the current run of unchanged commits
+    run: Option<Commit>,
+    any_found: bool,
+    // the oldest of the (newer) commits the function is missing from, since the last commit it was found in
+    missing: Option<String>,
+    // the last commit the function was found in, it is held back until the next one is found
+    // so that the commits searched before then can tell if it added the function
+    last: Option<Commit>,
+    pickaxe: Option<Regex>,
+    // the last commit that was not yet passed on because of the pickaxe, and how many matches it has
+    newer: Option<(Commit, usize)>,
+    any_passed: bool,
+}
+
+impl<F: FnMut(Commit)> Changes<F> {
+    fn new(found: F, options: &SearchOptions) -> Result<Self, Error> {
+        Ok(Self {
+            found,
+            collapse_unchanged: options.changes_only,
+            run: None,
+            any_found: false,
+            missing: None,
+            last: None,
+            pickaxe: options.pickaxe.as_ref().map(Pickaxe::compile).transpose()?,
+            newer: None,
+            any_passed: false,
+        })
+    }
+
+    fn searched(&mut self, searched: Searched) {
+        match searched {
+            Searched::Found(commit) => {
+                let commit = commit.with_deleted_in(self.missing.take());
+                if let Some(newer) = self.last.replace(commit) {
+                    self.push(newer);
                 }
-                Filter::Author(author) => *author == metadata.2,
-                Filter::AuthorEmail(email) => *email == metadata.3,
-                Filter::Message(message) => {
-                    metadata.0.contains(message)
-                        || message.contains(&metadata.0)
-                        || message == &metadata.0
+            }
+            Searched::Missing(hash) => {
+                if let Some(last) = &mut self.last {
+                    if last.parents().contains(&hash) {
+                        last.set_added();
+                    }
                 }
-                Filter::None => true,
-                _ => false,
+                self.missing = Some(hash);
             }
-        });
+        }
+    }
 
-    // todo use itertools to split into vec of oks and errs
-    // and report some of errors if no oks and if no oks and errs report no history found
-    let commits = commits
-        .filter_map(|i| {
-            let tree = sender(i.0, &th_repo.to_thread_local(), langs1, langs, file);
-            match tree {
-                Ok(tree) => {
-                    if tree.is_empty() {
-                        None?;
+    fn push(&mut self, commit: Commit) {
+        self.any_found = true;
+        if !self.collapse_unchanged {
+            self.pass(commit);
+            return;
+        }
+        match self.run.take() {
+            // the function could not have been deleted after the older commit if it has the same body as the newer one
+            Some(newer) if newer.same_bodies(&commit) && commit.deleted_in().is_none() => {
+                let unchanged_commits = newer.unchanged_commits() + 1;
+                self.run = Some(
+                    commit
+                        .with_unchanged_commits(unchanged_commits)
+                        .with_deleted_in(newer.deleted_in().map(str::to_string)),
+                );
+            }
+            newer => {
+                if let Some(newer) = newer {
+                    self.pass(newer);
+                }
+                self.run = Some(commit);
+            }
+        }
+    }
+
+    fn pass(&mut self, commit: Commit) {
+        let Some(pickaxe) = &self.pickaxe else {
+            self.any_passed = true;
+            (self.found)(commit);
+            return;
+        };
+        let matches = commit.count_matches(pickaxe);
+        if let Some((newer, newer_matches)) = self.newer.replace((commit, matches)) {
+            if newer_matches != matches {
+                self.any_passed = true;
+                (self.found)(newer);
+            }
+        }
+    }
+
+    fn finish(mut self, options: &SearchOptions) -> Result<(), Error> {
+        if let Some(last) = self.last.take() {
+            self.push(last);
+        }
+        if options.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        if !self.any_found {
+            return Err(Error::NoHistory);
+        }
+        if let Some(run) = self.run.take() {
+            self.pass(run);
+        }
+        // there is nothing before the oldest commit, so it added all of its matches
+        if let Some((oldest, matches)) = self.newer.take() {
+            if matches > 0 {
+                self.any_passed = true;
+                (self.found)(oldest);
+            }
+        }
+        if !self.any_passed {
+            return Err(Error::NoHistory);
+        }
+        Ok(())
+    }
+}
+
+/// returns the date of a `Filter::Date` (which can be in a `Filter::LocalTime`), as only the commit closest to it is searched
+fn closest_date(filter: &CompiledFilter<'_>) -> Option<(DateTime<FixedOffset>, DateSemantics)> {
+    match filter {
+        CompiledFilter::Date(date) => Some((*date, DateSemantics::Utc)),
+        CompiledFilter::LocalTime(filter) => {
+            closest_date(filter).map(|(date, _)| (date, DateSemantics::Local))
+        }
+        _ => None,
+    }
+}
+
+fn matches_filter(
+    filter: &CompiledFilter<'_>,
+    metadata: &CommitMetadata,
+    dates: DateSemantics,
+) -> bool {
+    match filter {
+        CompiledFilter::Date(_) => unreachable!(),
+        CompiledFilter::DateRange(range) => range.contains(metadata.committer.time, dates),
+        CompiledFilter::AuthorDateRange(range) => range.contains(metadata.author.time, dates),
+        CompiledFilter::And(filters) => filters
+            .iter()
+            .all(|filter| matches_filter(filter, metadata, dates)),
+        CompiledFilter::Or(filters) => filters
+            .iter()
+            .any(|filter| matches_filter(filter, metadata, dates)),
+        CompiledFilter::Not(filter) => !matches_filter(filter, metadata, dates),
+        CompiledFilter::LocalTime(filter) => matches_filter(filter, metadata, DateSemantics::Local),
+        CompiledFilter::Other(Filter::CommitHash(hash)) => *hash == metadata.hash,
+        CompiledFilter::AuthorRegex(_)
+        | CompiledFilter::Other(
+            Filter::Author(_)
+            | Filter::AuthorEmail(_)
+            | Filter::Committer(_)
+            | Filter::CommitterEmail(_),
+        ) => matches_person(
+            filter,
+            (&metadata.author.name, &metadata.author.email),
+            (&metadata.committer.name, &metadata.committer.email),
+        ),
+        CompiledFilter::Other(Filter::Message(message)) => {
+            metadata.message.contains(message)
+                || message.contains(&metadata.message)
+                || message == &metadata.message
+        }
+        CompiledFilter::Other(Filter::None) => true,
+        CompiledFilter::FileRegex(_) | CompiledFilter::Other(_) => false,
+    }
+}
+
+/// checks if the `author` or `committer` (as name and email) matches an author or committer filter,
+/// the name or email only have to contain the one of the filter (ignoring case).
+pub(crate) fn matches_person(
+    filter: &CompiledFilter<'_>,
+    (author, author_email): (&str, &str),
+    (committer, committer_email): (&str, &str),
+) -> bool {
+    let contains =
+        |haystack: &str, needle: &str| haystack.to_lowercase().contains(&needle.to_lowercase());
+    match filter {
+        CompiledFilter::Other(Filter::Author(name)) => contains(author, name),
+        CompiledFilter::Other(Filter::AuthorEmail(email)) => contains(author_email, email),
+        CompiledFilter::AuthorRegex(regex) => regex.is_match(&format!("{author} <{author_email}>")),
+        CompiledFilter::Other(Filter::Committer(name)) => contains(committer, name),
+        CompiledFilter::Other(Filter::CommitterEmail(email)) => contains(committer_email, email),
+        _ => false,
+    }
+}
+
+/// Goes through the commits one by one (newest first), and when the function cannot be found in a commit
+/// looks for a function with a very similar body in that commit, to keep on searching with its name.
+#[allow(clippy::too_many_arguments)]
+fn track_function_renames(
+    name: &str,
+    commits: impl Iterator<Item = WalkedCommit>,
+    th_repo: &Arc<gix::ThreadSafeRepository>,
+    langs: &[&dyn SupportedLanguage],
+    file: &FileFilterType,
+    filter: &CompiledFilter<'_>,
+    options: &SearchOptions,
+    found: &mut dyn FnMut(Commit),
+) -> Result<(), Error> {
+    let repo = &th_repo.to_thread_local();
+    let matcher = &FileMatcher::new(langs, file, options)?;
+    // for `Filter::Date` we still have to go through all the newer commits to know the name of the function at the closest commit
+    let (closest, commits): (_, Box<dyn Iterator<Item = WalkedCommit> + '_>) =
+        match closest_date(filter) {
+            Some((date, semantics)) => {
+                let commits = commits.collect::<Vec<_>>();
+                let commit = commits
+                    .iter()
+                    .min_by_key(|commit| semantics.distance(commit.2.committer.time, date));
+                let closest = commit.ok_or(Error::NoHistory)?.2.hash.clone();
+                (Some(closest), Box::new(commits.into_iter()))
+            }
+            None => (None, Box::new(commits)),
+        };
+    // the last commit where the function was found and whether it matches the filter,
+    // it is only passed on once the next one is found, as it could turn out to have the rename
+    let mut last_found: Option<(bool, Commit)> = None;
+    // the language, tree sitter kind and body of each version of the function in the last commit it was found in
+    let mut last_bodies: Vec<(String, &'static str, String)> = vec![];
+    let mut renamed_at = None;
+    // the oldest of the (newer) commits the function is missing from, since the last commit it was found in
+    let mut missing = None;
+    let mut current_name = name.to_string();
+    // the stats are shared between the caches for each name, to report them for the whole search
+    let stats = Arc::<Stats>::default();
+    let progress = ProgressReporter::new(options, stats.clone());
+    let mut commits = progress.walking(commits).peekable();
+    loop {
+        let instantiated = langs.instantiate_map(&current_name)?;
+        let cache = Cache::open(th_repo, &current_name, stats.clone(), options.disk_cache);
+        let renamed = loop {
+            if options.is_cancelled() {
+                break None;
+            }
+            let Some((id, path, metadata)) = commits.peek() else {
+                break None;
+            };
+            let id = *id;
+            let hash = metadata.hash.clone();
+            let path = followed_file(file, path.as_ref());
+            let files = sender(
+                id,
+                repo,
+                matcher,
+                &instantiated,
+                &path,
+                &cache,
+                &Execution::sequential(),
+            )?;
+            if files.is_empty() {
+                if renamed_at != Some(id) && !last_bodies.is_empty() {
+                    if let Some(old_name) = find_renamed_function(
+                        id,
+                        repo,
+                        matcher,
+                        &path,
+                        &instantiated,
+                        &last_bodies,
+                        &current_name,
+                    ) {
+                        renamed_at = Some(id);
+                        break Some(old_name);
+                    }
+                }
+                if let Some((_, newer)) = &mut last_found {
+                    if newer.parents().contains(&hash) {
+                        newer.set_added();
                     }
-                    Some(
-                        Commit::new(
-                            &i.1 .1,
-                            tree,
-                            &i.1 .4.to_rfc2822(),
-                            &i.1 .2,
-                            &i.1 .3,
-                            &i.1 .0,
-                        )
-                        .ok()?,
-                    )
                 }
-                Err(_) => None,
+                missing = Some(hash);
+                commits.next();
+                progress.commit_walked();
+                continue;
+            }
+            last_bodies = files
+                .iter()
+                .flat_map(|file| {
+                    file.result_kinds()
+                        .iter()
+                        .copied()
+                        .zip(file.clone())
+                        .map(|(kind, (_, body))| (file.language().to_string(), kind, body))
+                })
+                .collect();
+            let Some((_, path, metadata)) = commits.next() else {
+                break None;
+            };
+            progress.commit_walked();
+            let keep = closest.as_ref().map_or_else(
+                || matches_filter(filter, &metadata, DateSemantics::Utc),
+                |hash| *hash == metadata.hash,
+            );
+            let commit = metadata
+                .to_commit(files)
+                .with_tracked_path(path)
+                .with_deleted_in(missing.take());
+            if let Some((true, commit)) = last_found.replace((keep, commit)) {
+                found(commit);
             }
+        };
+        drop(cache);
+        drop(instantiated);
+        let Some(old_name) = renamed else {
+            break;
+        };
+        info!("{current_name} was renamed from {old_name}");
+        // the rename happened in the oldest commit that has the new name
+        if let Some((_, commit)) = &mut last_found {
+            commit.set_renamed_from(old_name.clone());
+        }
+        current_name = old_name;
+    }
+    if let Some((true, commit)) = last_found {
+        found(commit);
+    }
+    Ok(())
+}
+
+/// Looks through the files of the commit for a function whose body is very similar to one of the `last_bodies`, and returns its name.
+fn find_renamed_function(
+    id: ObjectId,
+    repo: &gix::Repository,
+    matcher: &FileMatcher<'_>,
+    file: &FileFilterType,
+    langs: &[InstantiatedLanguage<'_>],
+    last_bodies: &[(String, &'static str, String)],
+    current_name: &str,
+) -> Option<String> {
+    let files = files_in_tree(id, repo, matcher, file).ok()?;
+    files
+        .iter()
+        .filter_map(|(file_name, id)| {
+            let language = get_file_type_from_file(file_name, langs).ok()?;
+            let code = read_blob(*id, repo, file_name).ok()?;
+            let bodies = last_bodies
+                .iter()
+                .filter(|(lang, _, _)| *lang == language.name())
+                .collect::<Vec<_>>();
+            let kinds = bodies.iter().map(|(_, kind, _)| *kind).collect::<Vec<_>>();
+            find_named_nodes_of_kinds(&code, language, &kinds)
+                .ok()?
+                .into_iter()
+                .filter(|(_, name)| *name != current_name)
+                .flat_map(|(range, name)| {
+                    let candidate = &code[range.start_byte..range.end_byte];
+                    bodies
+                        .iter()
+                        .map(move |(_, _, body)| (body_similarity(body, candidate), name))
+                })
+                .max_by(|(a, _), (b, _)| a.total_cmp(b))
+                .map(|(similarity, name)| (similarity, name.to_string()))
         })
-        .collect::<Vec<_>>();
-    if commits.is_empty() {
-        Err("no history found")?;
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .filter(|(similarity, _)| *similarity >= RENAME_SIMILARITY)
+        .map(|(_, name)| name)
+}
+
+/// How similar two pieces of code are (from 0 to 1), based on how many tokens they have in common.
+#[allow(clippy::cast_precision_loss)]
+fn body_similarity(a: &str, b: &str) -> f64 {
+    fn tokens(code: &str) -> HashMap<&str, usize> {
+        let mut tokens = HashMap::new();
+        let mut start = None;
+        for (i, c) in code.char_indices() {
+            let is_word = c.is_alphanumeric() || c == '_';
+            if let Some(begin) = start.filter(|_| !is_word) {
+                *tokens.entry(&code[begin..i]).or_insert(0) += 1;
+                start = None;
+            }
+            if is_word {
+                start.get_or_insert(i);
+            } else if !c.is_whitespace() {
+                *tokens.entry(&code[i..i + c.len_utf8()]).or_insert(0) += 1;
+            }
+        }
+        if let Some(begin) = start {
+            *tokens.entry(&code[begin..]).or_insert(0) += 1;
+        }
+        tokens
+    }
+    let (a, b) = (tokens(a), tokens(b));
+    let total = a.values().sum::<usize>() + b.values().sum::<usize>();
+    if total == 0 {
+        return 1.0;
+    }
+    let common = a
+        .iter()
+        .map(|(token, count)| b.get(token).map_or(0, |other| *other.min(count)))
+        .sum::<usize>();
+    (2 * common) as f64 / total as f64
+}
+
+/// Finds the repository at `SearchOptions::repo`, or else the one we are in.
+fn discover_repo(options: &SearchOptions) -> Result<gix::Repository, Error> {
+    let path = options.repo.as_deref().unwrap_or_else(|| Path::new("."));
+    Ok(gix::discover(path)?)
+}
+
+/// Returns the commits to start walking the history from, and the commits that should be left out (with all their ancestors) for the revision.
+/// No revision means `HEAD`.
+fn revision_tips(
+    repo: &gix::Repository,
+    revision: Option<&str>,
+) -> Result<(Vec<ObjectId>, Vec<ObjectId>), Error> {
+    use gix::revision::plumbing::Spec;
+    let head = || -> Result<ObjectId, Error> {
+        Ok(repo
+            .head_id()
+            .map_err(Error::git("could not find HEAD"))?
+            .detach())
+    };
+    let Some(revision) = revision else {
+        return Ok((vec![head()?], vec![]));
+    };
+    let invalid = |e| Error::InvalidRevision(revision.to_string(), e);
+    // tags can point to tag objects, but we need the commits
+    let commit = |id: ObjectId| -> Result<gix::Commit<'_>, Error> {
+        repo.find_object(id)
+            .map_err(|e| invalid(e.into()))?
+            .peel_to_commit()
+            .map_err(|e| invalid(e.into()))
+    };
+    let parents = |id: ObjectId| -> Result<Vec<ObjectId>, Error> {
+        Ok(commit(id)?.parent_ids().map(gix::Id::detach).collect())
+    };
+    let spec = repo.rev_parse(revision).map_err(|e| invalid(e.into()))?;
+    Ok(match spec.detach() {
+        Spec::Include(id) => (vec![commit(id)?.id], vec![]),
+        Spec::Exclude(id) => (vec![head()?], vec![commit(id)?.id]),
+        Spec::Range { from, to } => (vec![commit(to)?.id], vec![commit(from)?.id]),
+        Spec::Merge { theirs, ours } => {
+            let (theirs, ours) = (commit(theirs)?.id, commit(ours)?.id);
+            (
+                vec![theirs, ours],
+                vec![repo
+                    .merge_base(theirs, ours)
+                    .map_err(|e| invalid(e.into()))?
+                    .detach()],
+            )
+        }
+        Spec::IncludeOnlyParents(id) => (parents(id)?, vec![]),
+        Spec::ExcludeParents(id) => (vec![commit(id)?.id], parents(id)?),
+    })
+}
+
+/// When following renames the file filter for a commit is the path the file had at that commit.
+fn followed_file(file: &FileFilterType, path: Option<&String>) -> FileFilterType {
+    path.map_or_else(
+        || file.clone(),
+        |path| FileFilterType::Absolute(path.clone()),
+    )
+}
+
+/// Returns the path of the followed file in the commit, and passes the path it had before to each parent of the commit
+/// (through `paths`), so the path is renamed only for the parents the commit renamed it from.
+///
+/// A commit that none of its children passed a path to is a tip, so it has the `tip` path.
+fn follow_path(
+    info: &gix::traverse::commit::Info,
+    tip: &str,
+    paths: &mut HashMap<ObjectId, String>,
+    merges: MergeMode,
+    repo: &gix::Repository,
+) -> String {
+    let path = paths.remove(&info.id).unwrap_or_else(|| tip.to_string());
+    let parents = if merges == MergeMode::FirstParent {
+        &info.parent_ids[..info.parent_ids.len().min(1)]
+    } else {
+        &info.parent_ids[..]
+    };
+    let tree = info
+        .id
+        .attach(repo)
+        .object()
+        .ok()
+        .and_then(|commit| commit.try_into_commit().ok()?.tree().ok());
+    for parent in parents {
+        let old = tree
+            .as_ref()
+            .and_then(|tree| renamed_from(tree, *parent, &path, repo));
+        if let Some(old) = &old {
+            info!("{path} was renamed from {old} in {}", info.id);
+        }
+        // with more than one child the newest one wins, as we go through the children newest first
+        paths
+            .entry(*parent)
+            .or_insert_with(|| old.unwrap_or_else(|| path.clone()));
+    }
+    path
+}
+
+/// Finds the path that the file at `path` in `tree` had in the `parent` commit, if the commit renamed or moved it.
+fn renamed_from(
+    tree: &Tree<'_>,
+    parent: ObjectId,
+    path: &str,
+    repo: &gix::Repository,
+) -> Option<String> {
+    let parent_tree = parent
+        .attach(repo)
+        .object()
+        .ok()?
+        .try_into_commit()
+        .ok()?
+        .tree()
+        .ok()?;
+    // only when the file appeared in this commit there can be a rename, so we don't bother diffing otherwise
+    tree.lookup_entry_by_path(path).ok()??;
+    if parent_tree.lookup_entry_by_path(path).ok()?.is_some() {
+        return None;
     }
-    let fh = FunctionHistory::new(name.to_string(), commits);
-    Ok(fh)
+    let mut source = None;
+    let mut changes = parent_tree.changes().ok()?;
+    changes.options(|opts| {
+        opts.track_path()
+            .track_rewrites(Some(gix::diff::Rewrites::default()));
+    });
+    // we cancel the diff once we found the rename, so the diff "failing" is expected
+    let _ = changes.for_each_to_obtain_tree(tree, |change| {
+        if let gix::object::tree::diff::Change::Rewrite {
+            source_location,
+            location,
+            copy: false,
+            ..
+        } = change
+        {
+            if location == path {
+                source = Some(source_location.to_string());
+                return Ok::<_, Infallible>(gix::object::tree::diff::Action::Cancel);
+            }
+        }
+        Ok(gix::object::tree::diff::Action::Continue)
+    });
+    source
 }
 
 fn sender(
     id: ObjectId,
     repo: &gix::Repository,
-    file_exts: &[&str],
+    matcher: &FileMatcher<'_>,
+    langs: &[InstantiatedLanguage<'_>],
+    file: &FileFilterType,
+    cache: &Cache,
+    execution: &Execution<'_>,
+) -> Result<Vec<ParsedFile>, Error> {
+    let tree = find_tree(id, repo)?;
+    search_tree(&tree, repo, "", matcher, langs, file, cache, execution)
+}
+
+/// Searches for the function in all the files in the tree.
+/// Subtrees that were already searched (most likely in the parent commit) are not looked through again,
+/// instead what was found there last time is used.
+#[allow(clippy::too_many_arguments)]
+fn search_tree(
+    tree: &Tree<'_>,
+    repo: &gix::Repository,
+    path: &str,
+    matcher: &FileMatcher<'_>,
     langs: &[InstantiatedLanguage<'_>],
+    filetype: &FileFilterType,
+    cache: &Cache,
+    execution: &Execution<'_>,
+) -> Result<Vec<ParsedFile>, Error> {
+    cache.subtree(tree.id, path, filetype, || {
+        let entries = tree_entries(tree, path, matcher, filetype)?;
+        let found = execution.search_entries(repo, &entries, |repo, (file, id, is_tree)| {
+            if *is_tree {
+                let tree = find_tree(*id, repo)?;
+                search_tree(
+                    &tree, repo, file, matcher, langs, filetype, cache, execution,
+                )
+            } else {
+                Ok(find_function_in_file(file, *id, repo, langs, cache)
+                    .into_iter()
+                    .collect())
+            }
+        })?;
+        Ok(found.concat())
+    })
+}
+
+/// Same as `search_tree`, but searches for many names at once (with the languages and the cache for each name),
+/// and returns what was found for each name.
+#[allow(clippy::too_many_arguments)]
+fn search_tree_many(
+    tree: &Tree<'_>,
+    repo: &gix::Repository,
+    path: &str,
+    matcher: &FileMatcher<'_>,
+    langs: &[Vec<InstantiatedLanguage<'_>>],
+    filetype: &FileFilterType,
+    caches: &[Cache],
+    execution: &Execution<'_>,
+) -> Result<Vec<Vec<ParsedFile>>, Error> {
+    // the subtree only has to be searched again if any of the names was not searched in it yet
+    let searched = caches
+        .iter()
+        .map(|cache| cache.get_subtree(tree.id, path, filetype))
+        .collect::<Option<Vec<_>>>();
+    if let Some(searched) = searched {
+        return Ok(searched);
+    }
+    let entries = tree_entries(tree, path, matcher, filetype)?;
+    let entries = execution.search_entries(repo, &entries, |repo, (file, id, is_tree)| {
+        if *is_tree {
+            let tree = find_tree(*id, repo)?;
+            search_tree_many(
+                &tree, repo, file, matcher, langs, filetype, caches, execution,
+            )
+        } else {
+            Ok(find_functions_in_file(file, *id, repo, langs, caches)
+                .into_iter()
+                .map(|file| file.into_iter().collect())
+                .collect())
+        }
+    })?;
+    let mut found = vec![vec![]; caches.len()];
+    for more in entries {
+        for (found, more) in found.iter_mut().zip(more) {
+            found.extend(more);
+        }
+    }
+    for (cache, found) in caches.iter().zip(&found) {
+        cache.insert_subtree(tree.id, path, filetype, found.clone());
+    }
+    Ok(found)
+}
+
+/// returns the entries of the tree that have to be searched (see `TreeEntries`)
+fn tree_entries(
+    tree: &Tree<'_>,
+    path: &str,
+    matcher: &FileMatcher<'_>,
+    filetype: &FileFilterType,
+) -> Result<Vec<(String, ObjectId, bool)>, Error> {
+    let mut entries = vec![];
+    for i in tree.iter() {
+        let i = i.map_err(Error::git("could not read tree entry"))?;
+        let file = format!(
+            "{path}{}{}",
+            if path.is_empty() { "" } else { "/" },
+            i.filename()
+        );
+        match &i.mode().kind() {
+            objs::tree::EntryKind::Tree if matcher.wanted_dir(&file) => {
+                entries.push((file, i.oid().to_owned(), true));
+            }
+            objs::tree::EntryKind::Blob if matcher.wanted_file(&file, filetype) => {
+                entries.push((file, i.oid().to_owned(), false));
+            }
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// returns the path and blob id of each file in the tree that matches the file filter and is of a supported language
+fn files_in_tree(
+    id: ObjectId,
+    repo: &gix::Repository,
+    matcher: &FileMatcher<'_>,
     file: &FileFilterType,
-) -> Result<Vec<ParsedFile>, String> {
-    let object = repo.find_object(id).map_err(|_| "failed to find object")?;
-    let tree = object.try_into_tree();
-    let binding = tree.unwrap();
-    traverse_tree(&binding, repo, "", file_exts, langs, file)
+) -> Result<Vec<(String, ObjectId)>, Error> {
+    let tree = find_tree(id, repo)?;
+    let mut files = Vec::new();
+    traverse_tree(&tree, repo, "", matcher, file, &mut files)?;
+    Ok(files)
+}
+
+/// returns the tree with the id
+fn find_tree(id: ObjectId, repo: &gix::Repository) -> Result<Tree<'_>, Error> {
+    repo.find_object(id)
+        .map_err(Error::git("could not find tree"))?
+        .try_into_tree()
+        .map_err(Error::git("could not read tree"))
 }
 
 #[inline]
@@ -336,16 +1867,13 @@ fn traverse_tree(
     tree: &Tree<'_>,
     repo: &gix::Repository,
     path: &str,
-    file_exts: &[&str],
-    langs: &[InstantiatedLanguage<'_>],
+    matcher: &FileMatcher<'_>,
     filetype: &FileFilterType,
-) -> Result<Vec<ParsedFile>, String> {
-    let files_exts_iter = file_exts.iter();
+    files: &mut Vec<(String, ObjectId)>,
+) -> Result<(), Error> {
     let treee_iter = tree.iter();
-    let mut files: Vec<_> = Vec::new();
-    let mut ret = Vec::new();
     for i in treee_iter {
-        let i = i.map_err(|_| "failed to get tree entry")?;
+        let i = i.map_err(Error::git("could not read tree entry"))?;
         // TODO: what should the path seperator be?
         let file = format!(
             "{path}{}{}",
@@ -353,69 +1881,35 @@ fn traverse_tree(
             i.filename()
         );
         match &i.mode().kind() {
-            objs::tree::EntryKind::Tree => {
-                let new = repo
-                    .find_object(i.oid())
-                    .map_err(|_| "Could not find object")?
-                    .try_into_tree()
-                    .map_err(|_| {
-                        format!("Could not find {} from object", stringify!(try_into_tree))
-                    })?;
-                ret.extend(traverse_tree(
-                    &new, repo, &file, file_exts, langs, filetype,
-                )?);
+            objs::tree::EntryKind::Tree if matcher.wanted_dir(&file) => {
+                let new = find_tree(i.oid().to_owned(), repo)?;
+                traverse_tree(&new, repo, &file, matcher, filetype, files)?;
             }
-            objs::tree::EntryKind::Blob => {
-                match &filetype {
-                    FileFilterType::Relative(ref path) => {
-                        if !file.ends_with(path) {
-                            info!("{file} was skipped because it was not in path {path}");
-                            continue;
-                        }
-                    }
-                    FileFilterType::Absolute(ref path) => {
-                        if &file != path {
-                            info!("{file} was skipped because it was not the same as path {path}");
-                            continue;
-                        }
-                    }
-                    FileFilterType::Directory(ref path) => {
-                        if !file.contains(path) {
-                            info!("{file} was skipped because it was not in dir {path}");
-                            continue;
-                        }
-                    }
-                    FileFilterType::None => {}
-                }
-
-                // we have to clone because any abosrbs elements
-                // TODO: better solution then clone each time
-                if !files_exts_iter
-                    .clone()
-                    .any(|ext| ends_with_cmp_no_case(&file, ext))
-                {
-                    info!(
-                        "{file} was skipped because it was not supported supported {file_exts:?}"
-                    );
-                    continue;
-                }
-
-                let obh = repo
-                    .find_object(i.oid())
-                    .map_err(|e| format!("failed to find object for file {file}: {e}"))
-                    .inspect_err(|e| warn!("{e}"))?;
-                let blob = obh
-                    .try_into_blob()
-                    .map_err(|e| format!("could not obtain file contents of {file}: {e}"))
-                    .inspect_err(|e| warn!("{e}"))?;
-                files.push((file, String::from_utf8_lossy(&blob.data).to_string()));
+            objs::tree::EntryKind::Blob if matcher.wanted_file(&file, filetype) => {
+                files.push((file, i.oid().to_owned()));
             }
             _ => {}
         }
     }
-    ret.extend(find_function_in_files_with_commit(&files, langs));
 
-    Ok(ret)
+    Ok(())
+}
+
+/// returns the contents of the file with the blob id
+fn read_blob(id: ObjectId, repo: &gix::Repository, file: &str) -> Result<String, Error> {
+    let obh = repo
+        .find_object(id)
+        .map_err(Error::git(&format!(
+            "failed to find object for file {file}"
+        )))
+        .inspect_err(|e| warn!("{e}"))?;
+    let blob = obh
+        .try_into_blob()
+        .map_err(Error::git(&format!(
+            "could not obtain file contents of {file}"
+        )))
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(String::from_utf8_lossy(&blob.data).to_string())
 }
 
 /// used for the `get_function_history` macro internally (you don't have to touch this)
@@ -425,6 +1919,7 @@ pub struct MacroOpts<'a, 'b> {
     pub filter: Filter,
     pub supported_languages: Vec<&'b dyn SupportedLanguage>,
     pub default_languages: bool,
+    pub options: SearchOptions,
 }
 
 impl Default for MacroOpts<'_, '_> {
@@ -435,6 +1930,7 @@ impl Default for MacroOpts<'_, '_> {
             filter: Filter::None,
             supported_languages: vec![],
             default_languages: true,
+            options: SearchOptions::default(),
         }
     }
 }
@@ -467,6 +1963,7 @@ impl Default for MacroOpts<'_, '_> {
 /// - file: `FileFilterType::None`
 /// - filter: `Filter::None`
 /// - language: `Language::All`
+/// - options: `SearchOptions::default()`
 #[macro_export]
 macro_rules! get_function_history {
     ($($variant:ident = $value:expr),*) => {{
@@ -476,11 +1973,12 @@ macro_rules! get_function_history {
         )*
         let mut supported = opts.supported_languages;
         supported.extend(if opts.default_languages { function_grep::supported_languages::predefined_languages() } else { &[] });
-        get_function_history(
+        $crate::get_function_history_with_options(
             opts.name,
             &opts.file,
             &opts.filter,
-            &supported
+            &supported,
+            &opts.options
         )
     }};
 }
@@ -489,63 +1987,197 @@ macro_rules! get_function_history {
 ///
 /// # Errors
 /// wiil return `Err`if it cannot find or read from a git repository
-pub fn get_git_info() -> Result<Vec<CommitInfo>, Box<dyn Error + Send + Sync>> {
-    let repo = gix::discover(".")?;
-    let mut tips = vec![];
-    let head = repo.head_commit()?;
-    tips.push(head.id);
-    let commit_iter = repo.rev_walk(tips);
-    let commits = commit_iter.all()?.filter_map(|i| match i {
-        Ok(i) => get_item_from_oid_option!(i, &repo, try_into_commit).map(|i| {
-            let Ok(author) = i.author() else { return None };
-            let Ok(message) = i.message() else {
-                return None;
-            };
-            let mut msg = message.title.to_string();
-            if let Some(msg_body) = message.body {
-                msg.push_str(&msg_body.to_string());
-            }
+pub fn get_git_info() -> Result<Vec<CommitInfo>, Error> {
+    get_git_info_with_options(&SearchOptions::default())
+}
 
-            Some(CommitInfo {
-                date: match i.time().map(|x| DateTime::from_timestamp(x.seconds, 0)) {
-                    Ok(Some(i)) => i,
-                    _ => return None,
-                },
-                hash: i.id,
-                author_email: author.email.to_string(),
-                author: author.name.to_string(),
-                message: msg,
+/// Same as `get_git_info`, but for the repository and revision in `options` (see `SearchOptions::repo` and `SearchOptions::revision`).
+///
+/// # Errors
+/// wiil return `Err`if it cannot find or read from a git repository, or if the revision is not valid
+pub fn get_git_info_with_options(options: &SearchOptions) -> Result<Vec<CommitInfo>, Error> {
+    get_git_info_in(&discover_repo(options)?, options.revision.as_deref())
+}
+
+/// Same as `get_git_info`, but for an already opened repository, and for the commits of `revision` instead of `HEAD`.
+///
+/// # Errors
+/// wiil return `Err`if it cannot read from the git repository, or if the revision is not valid
+pub fn get_git_info_in(
+    repo: &gix::Repository,
+    revision: Option<&str>,
+) -> Result<Vec<CommitInfo>, Error> {
+    let (tips, hidden) = revision_tips(repo, revision)?;
+    let commit_iter = repo.rev_walk(tips).with_hidden(hidden);
+    let mailmap = repo.open_mailmap();
+    commit_iter
+        .all()
+        .map_err(Error::git("could not walk the commits"))?
+        .map(|info| {
+            let commit = info
+                .map_err(Error::git("could not walk the commits"))?
+                .object()
+                .map_err(Error::git("could not read a commit"))?;
+            let metadata = CommitMetadata::read(&commit, &mailmap)?;
+            Ok(CommitInfo {
+                date: metadata.committer.time,
+                hash: commit.id,
+                message: metadata.message,
+                author: metadata.author.name,
+                author_email: metadata.author.email,
+                author_date: metadata.author.time,
+                committer: metadata.committer.name,
+                committer_email: metadata.committer.email,
             })
-        }),
-        Err(_) => None,
-    });
-    let commits = commits.flatten();
-    let commits = commits.collect::<Vec<_>>();
-    Ok(commits)
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone)]
 pub struct CommitInfo {
-    pub date: DateTime<Utc>,
+    /// when the commit was committed (which is later than `author_date` for rebased or cherry-picked commits)
+    pub date: DateTime<FixedOffset>,
     pub hash: ObjectId,
     pub message: String,
     pub author: String,
     pub author_email: String,
+    /// when the commit was originally authored
+    pub author_date: DateTime<FixedOffset>,
+    /// who applied the commit (like when rebasing, cherry-picking or applying a patch)
+    pub committer: String,
+    pub committer_email: String,
 }
 
 #[inline]
-// #[cfg_attr(feature = "cache", cached)]
-// function that takes a vec of files paths and there contents and a function name and uses find_function_in_file_with_commit to find the function in each file and returns a vec of the functions
-fn find_function_in_files_with_commit(
-    files: &[(String, String)],
+// function that takes a file path and its blob id and finds the function in the file
+// blobs that were already searched (in this or a previous search) are not parsed again, see `Cache`
+fn find_function_in_file(
+    file_path: &str,
+    id: ObjectId,
+    repo: &gix::Repository,
     langs: &[InstantiatedLanguage<'_>],
-) -> Vec<ParsedFile> {
-    // commenting out this parallelization seems to net a gain in performance with tree sitter
-    //#[cfg(feature = "parallel")]
-    //let t = files.par_iter();
-    //#[cfg(not(feature = "parallel"))]
-    let t = files.iter();
-    t.filter_map(|(file_path, fc)| ParsedFile::search_file_with_name(fc, file_path, langs).ok())
+    cache: &Cache,
+) -> Option<ParsedFile> {
+    let language = get_file_type_from_file(file_path, langs).ok()?;
+    // most files don't change between commits, so we most likely already searched this blob
+    cache
+        .parsed(language.name(), id, || {
+            search_blob(file_path, id, repo, language, cache).map(|file| cache.compact(file, id))
+        })
+        .map(|file| file.set_file_name(file_path))
+}
+
+/// Searches for the function in the blob, unless the blob was already searched in a previous search.
+fn search_blob(
+    file_path: &str,
+    id: ObjectId,
+    repo: &gix::Repository,
+    language: &InstantiatedLanguage<'_>,
+    cache: &Cache,
+) -> Option<ParsedFile> {
+    let cached = cache.found(language, id);
+    if cached.as_ref().is_some_and(|found| found.ranges.is_empty()) {
+        return None;
+    }
+    let fc = read_blob(id, repo, file_path).ok()?;
+    // cached ranges that do not fit the blob (if the cache file got damaged) are thrown away, and the blob is searched again
+    if let Some(file) = cached.and_then(|found| {
+        ParsedFile::from_results(&fc, file_path, language, found.ranges, found.kinds)
+    }) {
+        return Some(file);
+    }
+    cache.parsed_blob();
+    match ParsedFile::search_file(&fc, language) {
+        Ok(file) => {
+            cache.insert_found(language, id, Found::of(&file));
+            Some(file.set_file_name(file_path))
+        }
+        Err(function_grep::Error::NoResultsForSearch) => {
+            cache.insert_found(language, id, Found::default());
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+/// Same as `find_function_in_file`, but for many names at once (with the languages and the cache for each name).
+/// The blob is only parsed once for all the names that it was not searched for yet.
+fn find_functions_in_file(
+    file_path: &str,
+    id: ObjectId,
+    repo: &gix::Repository,
+    langs: &[Vec<InstantiatedLanguage<'_>>],
+    caches: &[Cache],
+) -> Vec<Option<ParsedFile>> {
+    let Some(languages) = langs
+        .iter()
+        .map(|langs| get_file_type_from_file(file_path, langs).ok())
+        .collect::<Option<Vec<_>>>()
+    else {
+        return vec![None; caches.len()];
+    };
+    let mut found = caches
+        .iter()
+        .zip(&languages)
+        .map(|(cache, language)| cache.get_parsed(language.name(), id))
+        .collect::<Vec<_>>();
+    // the blob is only read once, and only if it has to be
+    let mut code = None;
+    let read = |code: &mut Option<String>| {
+        if code.is_none() {
+            *code = read_blob(id, repo, file_path).ok();
+        }
+    };
+    for ((found, cache), language) in found.iter_mut().zip(caches).zip(&languages) {
+        if found.is_some() {
+            continue;
+        }
+        let Some(saved) = cache.found(language, id) else {
+            continue;
+        };
+        let parsed = if saved.ranges.is_empty() {
+            None
+        } else {
+            read(&mut code);
+            // cached ranges that do not fit the blob are searched for again below
+            let Some(file) = code.as_deref().and_then(|code| {
+                ParsedFile::from_results(code, file_path, language, saved.ranges, saved.kinds)
+            }) else {
+                continue;
+            };
+            Some(cache.compact(file, id))
+        };
+        cache.insert_parsed(language.name(), id, parsed.clone());
+        *found = Some(parsed);
+    }
+    let unsearched = (0..found.len())
+        .filter(|i| found[*i].is_none())
+        .collect::<Vec<_>>();
+    if let Some(first) = unsearched.first() {
+        read(&mut code);
+        caches[*first].parsed_blob();
+        let unsearched_languages = unsearched.iter().map(|i| languages[*i]).collect::<Vec<_>>();
+        let parsed = code
+            .as_deref()
+            .and_then(|code| ParsedFile::search_file_many(code, &unsearched_languages).ok());
+        for (n, i) in unsearched.iter().enumerate() {
+            let language = languages[*i].name();
+            let file = parsed
+                .as_ref()
+                .and_then(|parsed| parsed[n].clone())
+                .map(|file| caches[*i].compact(file, id));
+            // only what was actually searched is kept on disk
+            if parsed.is_some() {
+                let found = file.as_ref().map(Found::of).unwrap_or_default();
+                caches[*i].insert_found(languages[*i], id, found);
+            }
+            caches[*i].insert_parsed(language, id, file.clone());
+            found[*i] = Some(file);
+        }
+    }
+    found
+        .into_iter()
+        .map(|file| file.flatten().map(|file| file.set_file_name(file_path)))
         .collect()
 }
 
@@ -569,11 +2201,113 @@ impl<T> UnwrapToError<T> for Option<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    #[test]
-    fn found_function() {
-        let now = Utc::now();
-        let binding = FileFilterType::Relative("src/test_functions.rs".to_string());
-        let output = get_function_history(
+    use chrono::Utc;
+    use dates::DateRange;
+    use std::{cell::Cell, fs, process};
+
+    /// A repository in a temporary directory to test on, made with the `git` command line.
+    /// Each commit is made a minute after the one before it, so the history is the same every time.
+    struct Fixture {
+        dir: tempfile::TempDir,
+        commits: Cell<i64>,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let fixture = Self {
+                dir: tempfile::tempdir().unwrap(),
+                commits: Cell::new(0),
+            };
+            fixture.git(&["init", "--quiet", "--initial-branch=main"]);
+            fixture
+        }
+
+        /// runs git in the repository (as alice), and returns what it printed
+        fn git(&self, args: &[&str]) -> String {
+            let date = format!("{} +0000", 1_700_000_000 + 60 * self.commits.get());
+            self.git_at(args, &date)
+        }
+
+        /// same as `git`, but with `date` as the date of any commit it makes
+        fn git_at(&self, args: &[&str], date: &str) -> String {
+            let output = process::Command::new("git")
+                .args(args)
+                .current_dir(self.dir.path())
+                .env("GIT_CONFIG_GLOBAL", "/dev/null")
+                .env("GIT_CONFIG_NOSYSTEM", "1")
+                .env("GIT_AUTHOR_NAME", "Alice")
+                .env("GIT_AUTHOR_EMAIL", "alice@example.com")
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_NAME", "Alice")
+                .env("GIT_COMMITTER_EMAIL", "alice@example.com")
+                .env("GIT_COMMITTER_DATE", date)
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "git {args:?} failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            String::from_utf8(output.stdout).unwrap().trim().to_string()
+        }
+
+        fn write(&self, path: &str, text: &str) {
+            let path = self.dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+
+        /// commits everything in the worktree, and returns the hash of the commit
+        fn commit(&self, message: &str) -> String {
+            self.commits.set(self.commits.get() + 1);
+            self.git(&["add", "--all"]);
+            self.git(&["commit", "--quiet", "--allow-empty", "--message", message]);
+            self.git(&["rev-parse", "HEAD"])
+        }
+
+        /// same as `commit`, but committed at `date`
+        fn commit_at(&self, message: &str, date: &str) -> String {
+            self.git(&["add", "--all"]);
+            self.git_at(&["commit", "--quiet", "--message", message], date);
+            self.git(&["rev-parse", "HEAD"])
+        }
+
+        /// merges `branch` into the current branch (always with a merge commit), and returns the hash of the merge commit
+        fn merge(&self, branch: &str) -> String {
+            self.commits.set(self.commits.get() + 1);
+            self.git(&["merge", "--quiet", "--no-ff", "--message", "merge", branch]);
+            self.git(&["rev-parse", "HEAD"])
+        }
+
+        fn history(
+            &self,
+            name: &str,
+            file: &FileFilterType,
+            options: &SearchOptions,
+        ) -> Result<FunctionHistory, Error> {
+            get_function_history_in(
+                &gix::open(self.dir.path()).unwrap(),
+                name,
+                file,
+                &Filter::None,
+                function_grep::supported_languages::predefined_languages(),
+                options,
+            )
+        }
+    }
+
+    /// a rust file with the functions `handler` and `other`, with the numbers in their bodies
+    fn handlers(handler: usize, other: usize) -> String {
+        format!(
+            "fn handler() {{\n    let x = {handler};\n    println!(\"{{x}}\");\n}}\n\n\n\n\n\nfn other() {{\n    let y = {other};\n    println!(\"{{y}}\");\n}}\n"
+        )
+    }
+
+    #[test]
+    fn found_function() {
+        let now = Utc::now();
+        let binding = FileFilterType::Relative("src/test_functions.rs".to_string());
+        let output = get_function_history(
             "empty_test",
             &FileFilterType::None,
             &Filter::None,
@@ -621,6 +2355,1516 @@ mod tests {
             .to_string()
             .contains("is not a supported file"));
     }
+    #[test]
+    fn typed_errors() {
+        let langs = function_grep::supported_languages::predefined_languages();
+        let file = FileFilterType::Relative("src/test_functions.rs".to_string());
+        let search =
+            |name: &str, file: &FileFilterType, filter: &Filter, options: &SearchOptions| {
+                get_function_history_with_options(name, file, filter, langs, options).unwrap_err()
+            };
+        let default = SearchOptions::default();
+        assert!(matches!(
+            search("", &file, &Filter::None, &default),
+            Error::EmptyName
+        ));
+        assert!(matches!(
+            search("Not_a_function", &file, &Filter::None, &default),
+            Error::NoHistory
+        ));
+        assert!(matches!(
+            search(
+                "empty_test",
+                &FileFilterType::Absolute("src/test_functions.txt".to_string()),
+                &Filter::None,
+                &default
+            ),
+            Error::UnsupportedFile(..)
+        ));
+        assert!(matches!(
+            search(
+                "empty_test",
+                &file,
+                &Filter::Date("not a date".to_string()),
+                &default
+            ),
+            Error::InvalidDate(..)
+        ));
+        assert!(matches!(
+            search(
+                "empty_test",
+                &file,
+                &Filter::Language("rust".to_string()),
+                &default
+            ),
+            Error::InvalidFilter(_)
+        ));
+        assert!(matches!(
+            search(
+                "empty_test",
+                &file,
+                &Filter::None,
+                &SearchOptions {
+                    revision: Some("not-a-revision".to_string()),
+                    ..SearchOptions::default()
+                }
+            ),
+            Error::InvalidRevision(..)
+        ));
+        assert!(matches!(
+            search(
+                "empty_test",
+                &file,
+                &Filter::None,
+                &SearchOptions {
+                    repo: Some(std::env::temp_dir()),
+                    ..SearchOptions::default()
+                }
+            ),
+            Error::RepositoryNotFound(_)
+        ));
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        assert!(matches!(
+            search(
+                "empty_test",
+                &file,
+                &Filter::None,
+                &SearchOptions {
+                    cancel: Some(cancel),
+                    ..SearchOptions::default()
+                }
+            ),
+            Error::Cancelled
+        ));
+        let history = get_function_history("empty_test", &file, &Filter::None, langs).unwrap();
+        assert!(matches!(
+            history.filter_by(&Filter::Author("nobody".to_string())),
+            Err(Error::NoHistory)
+        ));
+        // the dates are parsed once up front, so a bad one is an error instead of matching nothing
+        assert!(matches!(
+            history.filter_by(&Filter::since("not a date")),
+            Err(Error::InvalidDate(..))
+        ));
+        assert!(matches!(
+            search(
+                "empty_test",
+                &file,
+                &Filter::Not(Box::new(Filter::until("not a date"))),
+                &default
+            ),
+            Error::InvalidDate(..)
+        ));
+    }
+
+    #[test]
+    fn follow_renames() {
+        let fixture = Fixture::new();
+        fixture.write("src/app.rs", &handlers(1, 1));
+        let added = fixture.commit("add handler");
+        fixture.write("src/app.rs", &handlers(2, 1));
+        let changed = fixture.commit("change handler");
+        fixture.git(&["mv", "src/app.rs", "src/lib.rs"]);
+        let moved = fixture.commit("move handler");
+        fixture.write("src/lib.rs", &handlers(3, 1));
+        let changed_again = fixture.commit("change handler again");
+
+        let file = FileFilterType::Absolute("src/lib.rs".to_string());
+        let follow = SearchOptions {
+            follow: true,
+            ..SearchOptions::default()
+        };
+        let history = fixture.history("handler", &file, &follow).unwrap();
+        assert_eq!(
+            history.list_commit_hashes(),
+            [&changed_again, &moved, &changed, &added]
+        );
+        let paths = history
+            .iter()
+            .map(|commit| commit.tracked_path().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            ["src/lib.rs", "src/lib.rs", "src/app.rs", "src/app.rs"]
+        );
+        // without following the history stops at the move
+        let history = fixture
+            .history("handler", &file, &SearchOptions::default())
+            .unwrap();
+        assert_eq!(history.list_commit_hashes(), [&changed_again, &moved]);
+    }
+
+    #[test]
+    fn follow_renames_on_branches() {
+        let fixture = Fixture::new();
+        fixture.write("src/app.rs", &handlers(1, 1));
+        let added = fixture.commit("add handler");
+        // the file is moved on a branch, while it is still changed on main
+        fixture.git(&["checkout", "--quiet", "-b", "feature"]);
+        fixture.git(&["mv", "src/app.rs", "src/lib.rs"]);
+        let moved = fixture.commit("move handler");
+        fixture.write("src/lib.rs", &handlers(2, 1));
+        let changed_on_branch = fixture.commit("change handler");
+        fixture.git(&["checkout", "--quiet", "main"]);
+        fixture.write("src/app.rs", &handlers(1, 2));
+        let changed_on_main = fixture.commit("change other");
+        let merge = fixture.merge("feature");
+
+        let history = fixture
+            .history(
+                "handler",
+                &FileFilterType::Absolute("src/lib.rs".to_string()),
+                &SearchOptions {
+                    follow: true,
+                    ..SearchOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            history.list_commit_hashes(),
+            [&merge, &changed_on_main, &changed_on_branch, &moved, &added]
+        );
+        let paths = history
+            .iter()
+            .map(|commit| commit.tracked_path().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "src/lib.rs",
+                "src/app.rs",
+                "src/lib.rs",
+                "src/lib.rs",
+                "src/app.rs"
+            ]
+        );
+    }
+
+    #[test]
+    fn follow_needs_absolute_file() {
+        let output = get_function_history_with_options(
+            "empty_test",
+            &FileFilterType::Relative("src/test_functions.rs".to_string()),
+            &Filter::None,
+            function_grep::supported_languages::predefined_languages(),
+            &SearchOptions {
+                follow: true,
+                ..SearchOptions::default()
+            },
+        );
+        assert!(output.is_err());
+    }
+
+    #[test]
+    fn follow_function_renames() {
+        let file = FileFilterType::Relative("src/lib.rs".to_string());
+        let follow = SearchOptions {
+            follow_function_renames: true,
+            ..SearchOptions::default()
+        };
+        let body = "() {\n    let x = 1;\n    println!(\"{x}\");\n}\n\nfn main() {}\n";
+        let fixture = Fixture::new();
+        fixture.write("src/lib.rs", &format!("fn old_handler{body}"));
+        let added = fixture.commit("add old_handler");
+        fixture.write("src/lib.rs", &format!("fn handler{body}"));
+        let renamed = fixture.commit("rename old_handler");
+        fixture.write(
+            "src/lib.rs",
+            &format!("fn handler{}", body.replace('1', "2")),
+        );
+        let changed = fixture.commit("change handler");
+
+        let history = fixture.history("handler", &file, &follow).unwrap();
+        assert_eq!(history.list_commit_hashes(), [&changed, &renamed, &added]);
+        let old_names = history.iter().map(Commit::renamed_from).collect::<Vec<_>>();
+        assert_eq!(old_names, [None, Some("old_handler"), None]);
+        let without = fixture
+            .history("handler", &file, &SearchOptions::default())
+            .unwrap();
+        assert_eq!(without.list_commit_hashes(), [&changed, &renamed]);
+
+        // a function with a different body is not the function before it was renamed
+        let fixture = Fixture::new();
+        fixture.write(
+            "src/lib.rs",
+            "fn old_handler() {\n    return;\n}\n\nfn main() {}\n",
+        );
+        fixture.commit("add old_handler");
+        fixture.write("src/lib.rs", &format!("fn handler{body}"));
+        let replaced = fixture.commit("replace old_handler");
+        assert!(
+            body_similarity(
+                "fn old_handler() {\n    return;\n}",
+                "fn handler() {\n    let x = 1;\n    println!(\"{x}\");\n}"
+            ) < RENAME_SIMILARITY
+        );
+        let history = fixture.history("handler", &file, &follow).unwrap();
+        assert_eq!(history.list_commit_hashes(), [&replaced]);
+        assert_eq!(history.get_commit().unwrap().renamed_from(), None);
+    }
+
+    #[test]
+    fn changes_only() {
+        let all = get_function_history(
+            "empty_test",
+            &FileFilterType::Relative("src/test_functions.rs".to_string()),
+            &Filter::None,
+            function_grep::supported_languages::predefined_languages(),
+        )
+        .unwrap();
+        let changes = get_function_history_with_options(
+            "empty_test",
+            &FileFilterType::Relative("src/test_functions.rs".to_string()),
+            &Filter::None,
+            function_grep::supported_languages::predefined_languages(),
+            &SearchOptions {
+                changes_only: true,
+                ..SearchOptions::default()
+            },
+        )
+        .unwrap();
+        let mut changes = changes;
+        let mut commits = vec![changes.get_commit().unwrap().clone()];
+        while changes.move_forward().is_some() {
+            commits.push(changes.get_commit().unwrap().clone());
+        }
+        let all = all.list_commit_hashes().len();
+        assert!(commits.len() <= all);
+        assert_eq!(
+            commits
+                .iter()
+                .map(|commit| commit.unchanged_commits() + 1)
+                .sum::<usize>(),
+            all
+        );
+        assert!(commits
+            .windows(2)
+            .all(|commits| !commits[0].same_bodies(&commits[1])));
+    }
+
+    #[test]
+    fn diff_hunks() {
+        use types::{diff_lines, DiffLine};
+        let text = (1..=12).map(|line| line.to_string()).collect::<Vec<_>>();
+        let old = text
+            .iter()
+            .enumerate()
+            .map(|(line, text)| (line + 1, text.as_str()))
+            .collect::<Vec<_>>();
+        let mut new = old.clone();
+        new[1].1 = "changed";
+        new.push((13, "added"));
+        let hunks = diff_lines("src/lib.rs", &old, &new);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].old_start, hunks[0].old_len), (1, 5));
+        assert_eq!(
+            hunks[0].lines[1..3],
+            [
+                DiffLine::Removed {
+                    old_line: 2,
+                    text: "2".to_string()
+                },
+                DiffLine::Added {
+                    new_line: 2,
+                    text: "changed".to_string()
+                }
+            ]
+        );
+        assert_eq!((hunks[1].new_start, hunks[1].new_len), (10, 4));
+        assert!(hunks.iter().all(|hunk| hunk.file == "src/lib.rs"));
+        assert!(diff_lines("src/lib.rs", &old, &old).is_empty());
+    }
+
+    #[test]
+    fn history_diffs() {
+        use types::DiffLine;
+        // `handler` is in two files, and twice in the first one
+        let nested = |outer, inner| {
+            format!(
+                "{}\nmod nested {{\n{}}}\n",
+                handlers(outer, 1),
+                handlers(inner, 1)
+            )
+        };
+        let fixture = Fixture::new();
+        fixture.write("src/a.rs", &nested(1, 1));
+        fixture.write("src/b.rs", &handlers(1, 1));
+        let added = fixture.commit("add handlers");
+        fixture.write("src/a.rs", &nested(1, 2));
+        fixture.write("src/b.rs", &handlers(3, 1));
+        let changed = fixture.commit("change handlers");
+        let output = fixture
+            .history("handler", &FileFilterType::None, &SearchOptions::default())
+            .unwrap();
+        let diffs = output.diffs();
+        assert_eq!(diffs.len(), output.list_commit_hashes().len());
+        // the oldest version is all new
+        let oldest = diffs.last().unwrap();
+        assert!(oldest.old_commit.is_none());
+        assert_eq!(oldest.removed(), 0);
+        assert!(oldest.added() > 0);
+        assert_eq!(output.diff_with_previous(), diffs.first().cloned());
+        assert!(output.diff(0, 0).unwrap().is_empty());
+
+        // each version is only compared with the one in the same place, with the line numbers of its own file
+        let hunks = diffs[0]
+            .hunks
+            .iter()
+            .map(|hunk| {
+                let lines = hunk
+                    .lines
+                    .iter()
+                    .filter(|line| !matches!(line, DiffLine::Context { .. }))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (hunk.file.as_str(), lines)
+            })
+            .collect::<Vec<_>>();
+        let change = |line, old: &str, new: &str| {
+            vec![
+                DiffLine::Removed {
+                    old_line: line,
+                    text: old.to_string(),
+                },
+                DiffLine::Added {
+                    new_line: line,
+                    text: new.to_string(),
+                },
+            ]
+        };
+        assert_eq!(
+            hunks,
+            [
+                ("src/a.rs", change(17, "    let x = 1;", "    let x = 2;")),
+                ("src/b.rs", change(2, "    let x = 1;", "    let x = 3;")),
+            ]
+        );
+        let text = diffs[0].to_string();
+        assert!(text.contains(&format!("--- {added}:src/a.rs\n+++ {changed}:src/a.rs\n")));
+        assert!(text.contains(&format!("--- {added}:src/b.rs\n+++ {changed}:src/b.rs\n")));
+    }
+
+    #[test]
+    #[cfg(feature = "cache")]
+    fn cached_search() {
+        let repo = Fixture::new();
+        repo.write("src/lib.rs", &handlers(1, 1));
+        repo.commit("add handler");
+        repo.write("src/lib.rs", &handlers(2, 1));
+        repo.commit("change handler");
+        // returns the history and how many blobs had to be parsed
+        let search = |disk_cache| {
+            let parsed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let sink = {
+                let parsed = parsed.clone();
+                ProgressSink::new(move |progress| {
+                    parsed.fetch_max(progress.blobs_parsed, std::sync::atomic::Ordering::Relaxed);
+                })
+            };
+            let history = repo
+                .history(
+                    "handler",
+                    &FileFilterType::Relative("src/lib.rs".to_string()),
+                    &SearchOptions {
+                        disk_cache,
+                        progress: Some(sink),
+                        ..SearchOptions::default()
+                    },
+                )
+                .unwrap();
+            (history, parsed.load(std::sync::atomic::Ordering::Relaxed))
+        };
+        let caches = repo.dir.path().join(".git/function-history-cache");
+        // nothing is written to disk unless asked for
+        let (uncached, parsed) = search(false);
+        assert_eq!(parsed, 2);
+        assert!(!caches.exists());
+        let (first, parsed) = search(true);
+        assert_eq!(parsed, 2);
+        // the second search uses what the first one cached
+        let (second, parsed) = search(true);
+        assert_eq!(parsed, 0);
+        for history in [&first, &second] {
+            assert_eq!(uncached.list_commit_hashes(), history.list_commit_hashes());
+            assert_eq!(uncached.to_string(), history.to_string());
+        }
+        // the kinds of the functions are cached as well
+        for history in [&first, &second] {
+            let file = history.get_commit().unwrap().get_file().unwrap();
+            assert_eq!(file.result_kinds(), ["function_item"]);
+        }
+        let cache = caches
+            .join(format!("v{}", cache::FORMAT_VERSION))
+            .join("68616e646c6572")
+            .join("Rust");
+        let text = fs::read_to_string(&cache).unwrap();
+        let (fingerprint, blobs) = text.split_once('\n').unwrap();
+        // ranges that do not fit the blob anymore are thrown away, and the blob is parsed again
+        let damaged = blobs
+            .lines()
+            .map(|line| [&line[..40], " 0,100000,0,0,0,0\n"].concat())
+            .collect::<String>();
+        fs::write(&cache, format!("{fingerprint}\n{damaged}")).unwrap();
+        let (reparsed, parsed) = search(true);
+        assert_eq!(parsed, 2);
+        assert_eq!(uncached.to_string(), reparsed.to_string());
+        // as are caches made with another query or grammar
+        fs::write(&cache, format!("{:016x}\n{blobs}", 0)).unwrap();
+        let (reparsed, parsed) = search(true);
+        assert_eq!(parsed, 2);
+        assert_eq!(uncached.to_string(), reparsed.to_string());
+        assert_eq!(search(true).1, 0);
+    }
+
+    #[test]
+    fn revisions() {
+        let search = |revision: &str| {
+            get_function_history_with_options(
+                "empty_test",
+                &FileFilterType::Relative("src/test_functions.rs".to_string()),
+                &Filter::None,
+                function_grep::supported_languages::predefined_languages(),
+                &SearchOptions {
+                    revision: Some(revision.to_string()),
+                    ..SearchOptions::default()
+                },
+            )
+        };
+        let head = search("HEAD").unwrap();
+        let all = get_function_history(
+            "empty_test",
+            &FileFilterType::Relative("src/test_functions.rs".to_string()),
+            &Filter::None,
+            function_grep::supported_languages::predefined_languages(),
+        )
+        .unwrap();
+        assert_eq!(head.list_commit_hashes(), all.list_commit_hashes());
+        // an empty range
+        assert!(search("HEAD..HEAD").is_err());
+        assert!(search("not-a-revision").is_err());
+        assert_eq!(
+            get_git_info_with_options(&SearchOptions {
+                revision: Some("HEAD^!".to_string()),
+                ..SearchOptions::default()
+            })
+            .unwrap()
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn path_filters() {
+        assert!(paths::in_directory("src/app/main.rs", "app"));
+        assert!(paths::in_directory("src/app/main.rs", "src/app/"));
+        assert!(!paths::in_directory("src/mapper.rs", "app"));
+        assert!(paths::ends_with_path("src/app.rs", "app.rs"));
+        assert!(!paths::ends_with_path("src/myapp.rs", "app.rs"));
+        assert!(paths::glob_matches(
+            "src/**/handlers/*.rs",
+            "src/handlers/user.rs"
+        ));
+        assert!(paths::glob_matches(
+            "src/**/handlers/*.rs",
+            "src/api/v1/handlers/user.rs"
+        ));
+        assert!(!paths::glob_matches(
+            "src/**/handlers/*.rs",
+            "src/handlers/user/mod.rs"
+        ));
+
+        let langs = function_grep::supported_languages::predefined_languages();
+        let search = |file: FileFilterType, exclude: &[&str]| {
+            get_function_history_with_options(
+                "empty_test",
+                &file,
+                &Filter::None,
+                langs,
+                &SearchOptions {
+                    exclude: exclude.iter().map(ToString::to_string).collect(),
+                    ..SearchOptions::default()
+                },
+            )
+        };
+        let all = search(
+            FileFilterType::Relative("src/test_functions.rs".to_string()),
+            &[],
+        )
+        .unwrap();
+        for file in [
+            FileFilterType::Glob("**/src/test_*.rs".to_string()),
+            FileFilterType::Regex(r"src/test_functions\.rs$".to_string()),
+        ] {
+            assert_eq!(
+                search(file, &[]).unwrap().list_commit_hashes(),
+                all.list_commit_hashes()
+            );
+        }
+        assert!(matches!(
+            search(FileFilterType::Directory("rc".to_string()), &[]),
+            Err(Error::NoHistory)
+        ));
+        assert!(matches!(
+            search(FileFilterType::Regex("(".to_string()), &[]),
+            Err(Error::InvalidFilter(_))
+        ));
+        // excluded directories are not searched at all
+        assert!(matches!(
+            search(
+                FileFilterType::Relative("src/test_functions.rs".to_string()),
+                &["src/"]
+            ),
+            Err(Error::NoHistory)
+        ));
+        assert!(matches!(
+            search(
+                FileFilterType::Relative("src/test_functions.rs".to_string()),
+                &["*_functions.rs"]
+            ),
+            Err(Error::NoHistory)
+        ));
+
+        let glob = Filter::FileGlob("**/test_functions.rs".to_string());
+        let regex = Filter::FileRegex("test_functions".to_string());
+        assert_eq!(
+            all.filter_by(&glob.and(regex))
+                .unwrap()
+                .list_commit_hashes(),
+            all.list_commit_hashes()
+        );
+        assert!(matches!(
+            all.filter_by(&Filter::Directory("rc".to_string())),
+            Err(Error::NoHistory)
+        ));
+    }
+
+    #[test]
+    fn author_filters() {
+        let mailmap = gix::mailmap::Snapshot::from_bytes(
+            b"# comment\n\
+             Alice Smith <alice@example.com>\n\
+             <alice@example.com> <alice@old.example.com>\n\
+             Alice Smith <alice@example.com> ally <ALLY@work.example.com>\n",
+        );
+        let resolve = |name: &str, email: &str| {
+            let signature = gix::actor::SignatureRef {
+                name: name.into(),
+                email: email.into(),
+                time: "1700000000 +0000",
+            };
+            let signature = Signature::read(signature, &mailmap).unwrap();
+            (signature.name, signature.email)
+        };
+        assert_eq!(
+            resolve("alice", "alice@example.com"),
+            ("Alice Smith".to_string(), "alice@example.com".to_string())
+        );
+        assert_eq!(
+            resolve("alice", "alice@old.example.com"),
+            ("alice".to_string(), "alice@example.com".to_string())
+        );
+        assert_eq!(
+            resolve("Ally", "ally@work.example.com"),
+            ("Alice Smith".to_string(), "alice@example.com".to_string())
+        );
+        // the entry is only for ally, so someone else with the same email is not mapped
+        // (the email can get the case it has in the .mailmap, emails are compared ignoring case anyway)
+        let (name, email) = resolve("someone", "ally@work.example.com");
+        assert_eq!(name, "someone");
+        assert!(email.eq_ignore_ascii_case("ally@work.example.com"));
+
+        let info = get_git_info().unwrap();
+        let author = &info.last().unwrap().author;
+        let langs = function_grep::supported_languages::predefined_languages();
+        let search = |filter: &Filter| {
+            get_function_history(
+                "empty_test",
+                &FileFilterType::Relative("src/test_functions.rs".to_string()),
+                filter,
+                langs,
+            )
+        };
+        let by_name = search(&Filter::Author(
+            author.chars().skip(1).collect::<String>().to_lowercase(),
+        ))
+        .unwrap();
+        let by_regex = search(&Filter::AuthorRegex(format!(
+            "(?i)^{}",
+            regex::escape(&author.to_uppercase())
+        )))
+        .unwrap();
+        assert_eq!(by_name.list_commit_hashes(), by_regex.list_commit_hashes());
+        let filtered = by_name
+            .filter_by(&Filter::Author(author.to_uppercase()))
+            .unwrap();
+        assert_eq!(filtered.list_commit_hashes(), by_name.list_commit_hashes());
+        assert!(matches!(
+            search(&Filter::AuthorRegex("(".to_string())),
+            Err(Error::InvalidFilter(_))
+        ));
+        // the regexes are compiled once up front, so a bad one is an error instead of matching nothing
+        for filter in [
+            Filter::AuthorRegex("(".to_string()),
+            Filter::FileRegex("(".to_string()),
+        ] {
+            assert!(matches!(
+                by_name.filter_by(&filter),
+                Err(Error::InvalidFilter(_))
+            ));
+        }
+
+        // the mailmap is read like git does, so `mailmap.file` is used as well
+        let fixture = Fixture::new();
+        fixture.write("src/lib.rs", &handlers(1, 1));
+        fixture.commit("add handler");
+        let people = fixture.dir.path().join("people.mailmap");
+        fs::write(&people, "Alice Smith <alice@example.com>\n").unwrap();
+        fixture.git(&["config", "mailmap.file", people.to_str().unwrap()]);
+        let history = fixture
+            .history(
+                "handler",
+                &FileFilterType::Relative("src/lib.rs".to_string()),
+                &SearchOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(history.get_commit().unwrap().author(), "Alice Smith");
+    }
+
+    #[test]
+    fn committer_filters() {
+        let info = get_git_info().unwrap();
+        let first = info.last().unwrap();
+        assert!(!first.committer.is_empty() && !first.committer_email.is_empty());
+        let langs = function_grep::supported_languages::predefined_languages();
+        let search = |filter: &Filter| {
+            get_function_history(
+                "empty_test",
+                &FileFilterType::Relative("src/test_functions.rs".to_string()),
+                filter,
+                langs,
+            )
+        };
+        let all = search(&Filter::None).unwrap();
+        let commit = all.get_commit().unwrap();
+        assert!(commit.get_metadata().contains_key("author"));
+        assert!(commit.author_date() <= commit.committer_date());
+        for filter in [
+            Filter::Committer(commit.committer().to_uppercase()),
+            Filter::CommitterEmail(commit.committer_email().to_string()),
+            Filter::AuthorDateRange("2000-01-01".to_string(), String::new()),
+        ] {
+            assert_eq!(
+                search(&filter).unwrap().list_commit_hashes(),
+                all.list_commit_hashes()
+            );
+            assert_eq!(
+                all.filter_by(&filter).unwrap().list_commit_hashes(),
+                all.list_commit_hashes()
+            );
+        }
+        assert!(matches!(
+            search(&Filter::AuthorDateRange(
+                String::new(),
+                "2000-01-01".to_string()
+            )),
+            Err(Error::NoHistory)
+        ));
+        assert!(matches!(
+            all.filter_by(&Filter::Committer("nobody at all".to_string())),
+            Err(Error::NoHistory)
+        ));
+    }
+
+    #[test]
+    fn missing_objects() {
+        // removes the loose object with the id from the repository
+        let remove = |fixture: &Fixture, id: &str| {
+            let objects = fixture.dir.path().join(".git/objects");
+            fs::remove_file(objects.join(&id[..2]).join(&id[2..])).unwrap();
+        };
+        let fixture = Fixture::new();
+        fixture.write("src/lib.rs", &handlers(1, 1));
+        fixture.commit("add handler");
+        fixture.write("src/lib.rs", &handlers(2, 1));
+        fixture.commit("change handler");
+        let search =
+            || fixture.history("handler", &FileFilterType::None, &SearchOptions::default());
+        assert!(search().is_ok());
+        // a tree of the older commit cannot be searched
+        remove(&fixture, &fixture.git(&["rev-parse", "HEAD~:src"]));
+        assert!(matches!(search(), Err(Error::Git(..))));
+        // the older commit cannot be walked
+        remove(&fixture, &fixture.git(&["rev-parse", "HEAD~"]));
+        assert!(matches!(search(), Err(Error::Git(..))));
+        assert!(matches!(
+            get_git_info_in(&gix::open(fixture.dir.path()).unwrap(), None),
+            Err(Error::Git(..))
+        ));
+    }
+
+    #[test]
+    fn date_formats() {
+        let now = DateTime::parse_from_rfc3339("2024-03-15T12:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let date = |date| dates::parse_date_at(date, now).unwrap().to_rfc3339();
+        assert_eq!(
+            date("Fri, 1 Mar 2024 10:00:00 +0200"),
+            "2024-03-01T10:00:00+02:00"
+        );
+        assert_eq!(
+            date("2024-03-01T10:00:00+02:00"),
+            "2024-03-01T10:00:00+02:00"
+        );
+        assert_eq!(date("2024-03-01 10:00"), "2024-03-01T10:00:00+00:00");
+        assert_eq!(date("2024-03-01"), "2024-03-01T00:00:00+00:00");
+        assert_eq!(date("now"), "2024-03-15T12:30:00+00:00");
+        assert_eq!(date("Yesterday"), "2024-03-14T00:00:00+00:00");
+        assert_eq!(date("2 weeks ago"), "2024-03-01T12:30:00+00:00");
+        assert_eq!(date("1.month.ago"), "2024-02-15T12:30:00+00:00");
+        assert!(matches!(
+            dates::parse_date_at("2 fortnights ago", now),
+            Err(Error::InvalidDate(..))
+        ));
+
+        let file = FileFilterType::Relative("src/test_functions.rs".to_string());
+        let langs = function_grep::supported_languages::predefined_languages();
+        let all = get_function_history("empty_test", &file, &Filter::None, langs).unwrap();
+        let since =
+            get_function_history("empty_test", &file, &Filter::since("2000-01-01"), langs).unwrap();
+        assert_eq!(since.list_commit_hashes(), all.list_commit_hashes());
+        assert!(matches!(
+            get_function_history("empty_test", &file, &Filter::until("2000-01-01"), langs),
+            Err(Error::NoHistory)
+        ));
+        assert!(matches!(
+            get_function_history(
+                "empty_test",
+                &file,
+                &Filter::DateRange("today".to_string(), "1 year ago".to_string()),
+                langs
+            ),
+            Err(Error::InvalidDateRange(..))
+        ));
+        assert_eq!(
+            all.filter_by(&Filter::since("2000-01-01").and(Filter::until("now")))
+                .unwrap()
+                .list_commit_hashes(),
+            all.list_commit_hashes()
+        );
+    }
+
+    #[test]
+    fn date_range_end_day() {
+        let fixture = Fixture::new();
+        fixture.write("src/lib.rs", &handlers(1, 1));
+        let before = fixture.commit_at("add handler", "2024-01-30T12:00:00+00:00");
+        fixture.write("src/lib.rs", &handlers(2, 1));
+        let midday = fixture.commit_at("change handler", "2024-01-31T12:00:00+00:00");
+        fixture.write("src/lib.rs", &handlers(3, 1));
+        fixture.commit_at("change handler again", "2024-02-01T00:00:00+00:00");
+        let history = |filter: &Filter| {
+            get_function_history_in(
+                &gix::open(fixture.dir.path()).unwrap(),
+                "handler",
+                &FileFilterType::None,
+                filter,
+                function_grep::supported_languages::predefined_languages(),
+                &SearchOptions::default(),
+            )
+            .unwrap()
+            .list_commit_hashes()
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+        };
+        // the end day is included until midnight, but not the start of the next day
+        assert_eq!(
+            history(&Filter::until("2024-01-31")),
+            [midday.as_str(), before.as_str()]
+        );
+        assert_eq!(
+            history(&Filter::DateRange(
+                "2024-01-31".to_string(),
+                "2024-01-31".to_string()
+            )),
+            [midday.as_str()]
+        );
+        // with a time the end is that point in time
+        assert_eq!(
+            history(&Filter::until("2024-01-31T11:00:00+00:00")),
+            [before.as_str()]
+        );
+        assert!(matches!(
+            DateRange::parse("2024-02-01", "2024-01-31"),
+            Err(Error::InvalidDateRange(..))
+        ));
+    }
+
+    #[test]
+    fn local_time() {
+        // 1am in UTC+2 is still the day before in UTC
+        let early = DateTime::parse_from_rfc3339("2024-03-01T01:00:00+02:00").unwrap();
+        let range = DateRange::parse("2024-03-01", "").unwrap();
+        assert!(!range.contains(early, DateSemantics::Utc));
+        assert!(range.contains(early, DateSemantics::Local));
+        let date = dates::parse_date("2024-03-01T01:00").unwrap();
+        assert_eq!(DateSemantics::Local.distance(early, date), 0);
+        assert_eq!(DateSemantics::Utc.distance(early, date), 2 * 60 * 60);
+
+        let info = get_git_info().unwrap();
+        let file = FileFilterType::Relative("src/test_functions.rs".to_string());
+        let langs = function_grep::supported_languages::predefined_languages();
+        let all = get_function_history("empty_test", &file, &Filter::None, langs).unwrap();
+        let commit = all.get_commit().unwrap();
+        // the dates keep the timezone they were committed in
+        let committed = info
+            .iter()
+            .find(|info| info.hash.to_string() == commit.get_metadata()["commit hash"])
+            .unwrap();
+        assert_eq!(committed.date, commit.committer_date());
+        assert_eq!(committed.date.offset(), commit.committer_date().offset());
+        let since = Filter::since("2000-01-01").local_time();
+        assert_eq!(
+            get_function_history("empty_test", &file, &since, langs)
+                .unwrap()
+                .list_commit_hashes(),
+            all.list_commit_hashes()
+        );
+        assert_eq!(
+            all.filter_by(&since).unwrap().list_commit_hashes(),
+            all.list_commit_hashes()
+        );
+        let closest = Filter::Date(commit.committer_date().to_rfc3339()).local_time();
+        assert_eq!(
+            get_function_history("empty_test", &file, &closest, langs)
+                .unwrap()
+                .list_commit_hashes(),
+            [commit.get_metadata()["commit hash"].as_str()]
+        );
+        assert!(matches!(
+            get_function_history(
+                "empty_test",
+                &file,
+                &Filter::until("2000-01-01").local_time(),
+                langs
+            ),
+            Err(Error::NoHistory)
+        ));
+    }
+
+    #[test]
+    fn combined_filters() {
+        let file = FileFilterType::Relative("src/test_functions.rs".to_string());
+        let langs = function_grep::supported_languages::predefined_languages();
+        let all = get_function_history("empty_test", &file, &Filter::None, langs).unwrap();
+        let hashes = all.list_commit_hashes();
+        let either =
+            Filter::CommitHash(hashes[0].to_string()).or(Filter::CommitHash(hashes[1].to_string()));
+        let found = get_function_history("empty_test", &file, &either, langs).unwrap();
+        assert_eq!(found.list_commit_hashes(), &hashes[..2]);
+        assert_eq!(
+            all.filter_by(&either).unwrap().list_commit_hashes(),
+            &hashes[..2]
+        );
+        let neither = get_function_history("empty_test", &file, &!either, langs).unwrap();
+        assert_eq!(neither.list_commit_hashes(), &hashes[2..]);
+
+        // filters on the files and on the commits can be mixed after searching
+        let language = all.get_commit().unwrap().get_file().unwrap().language();
+        let first = || Filter::CommitHash(hashes[0].to_string());
+        let rust = || Filter::Language(language.to_string());
+        assert_eq!(
+            all.filter_by(&first().and(rust()))
+                .unwrap()
+                .list_commit_hashes(),
+            &hashes[..1]
+        );
+        assert!(matches!(
+            all.filter_by(&first().and(!rust())),
+            Err(Error::NoHistory)
+        ));
+
+        // but only filters on the commits can be used when searching
+        for filter in [
+            Filter::None.and(rust()),
+            Filter::None.or(Filter::Date("Sat, 1 Jan 2022 00:00:00 +0000".to_string())),
+        ] {
+            assert!(matches!(
+                get_function_history("empty_test", &file, &filter, langs),
+                Err(Error::InvalidFilter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn streaming() {
+        let file = FileFilterType::Relative("src/test_functions.rs".to_string());
+        let langs = function_grep::supported_languages::predefined_languages();
+        let all = get_function_history("empty_test", &file, &Filter::None, langs).unwrap();
+        let mut streamed = vec![];
+        stream_function_history(
+            "empty_test",
+            &file,
+            &Filter::None,
+            langs,
+            &SearchOptions::default(),
+            |commit| streamed.push(commit.get_metadata()["commit hash"].clone()),
+        )
+        .unwrap();
+        // in the same order as they were walked
+        assert_eq!(streamed, all.list_commit_hashes());
+
+        let cancel = CancellationToken::new();
+        let options = SearchOptions {
+            cancel: Some(cancel.clone()),
+            ..SearchOptions::default()
+        };
+        let mut found = 0;
+        let cancelled =
+            stream_function_history("empty_test", &file, &Filter::None, langs, &options, |_| {
+                found += 1;
+                cancel.cancel();
+            });
+        assert!(cancelled.is_err());
+        assert!(found >= 1);
+        // the token stays cancelled
+        assert!(get_function_history_with_options(
+            "empty_test",
+            &file,
+            &Filter::None,
+            langs,
+            &options
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn progress() {
+        let reports = Arc::new(std::sync::Mutex::new(vec![]));
+        let sink = {
+            let reports = reports.clone();
+            ProgressSink::new(move |progress| reports.lock().unwrap().push(progress))
+        };
+        let fixture = Fixture::new();
+        fixture.write("tests/other.rs", &handlers(1, 1));
+        for handler in 1..=3 {
+            fixture.write("src/lib.rs", &handlers(handler, 1));
+            fixture.commit("change handler");
+        }
+        let options = SearchOptions {
+            progress: Some(sink),
+            parallelism: Parallelism {
+                strategy: ParallelStrategy::Sequential,
+                ..Parallelism::default()
+            },
+            ..SearchOptions::default()
+        };
+        fixture
+            .history("handler", &FileFilterType::None, &options)
+            .unwrap();
+        let reports = reports.lock().unwrap().clone();
+        let mut walked = reports.iter().map(|p| p.commits_walked).collect::<Vec<_>>();
+        walked.sort_unstable();
+        let last = reports.iter().max_by_key(|p| p.commits_walked).unwrap();
+        // every commit is reported once
+        assert_eq!(walked, (1..=last.total_commits).collect::<Vec<_>>());
+        assert!(reports.iter().all(|p| p.commits_walked <= p.total_commits));
+        assert_eq!(last.total_commits, 3);
+        // the commits are walked as they are searched, so the total is only known once the walk gets to the last commit
+        assert_eq!(reports[0].total_commits, 1);
+        assert!(reports[0].fraction().is_none());
+        assert!(last.done());
+        assert!((last.fraction().unwrap() - 1.0).abs() < f32::EPSILON);
+        // the tests did not change between commits
+        assert!(last.cache_hits > 0);
+    }
+
+    #[test]
+    fn merge_modes() {
+        let fixture = Fixture::new();
+        fixture.write("src/lib.rs", &handlers(1, 1));
+        let added = fixture.commit("add handler");
+        fixture.git(&["checkout", "--quiet", "-b", "feature"]);
+        fixture.write("src/lib.rs", &handlers(2, 1));
+        let changed_on_branch = fixture.commit("change handler");
+        fixture.git(&["checkout", "--quiet", "main"]);
+        fixture.write("src/lib.rs", &handlers(1, 2));
+        let changed_on_main = fixture.commit("change other");
+        let merge = fixture.merge("feature");
+
+        let search = |merges| {
+            fixture
+                .history(
+                    "handler",
+                    &FileFilterType::Relative("src/lib.rs".to_string()),
+                    &SearchOptions {
+                        merges,
+                        ..SearchOptions::default()
+                    },
+                )
+                .unwrap()
+        };
+        let all = search(MergeMode::All);
+        assert_eq!(
+            all.list_commit_hashes(),
+            [&merge, &changed_on_main, &changed_on_branch, &added]
+        );
+        assert_eq!(
+            search(MergeMode::FirstParent).list_commit_hashes(),
+            [&merge, &changed_on_main, &added]
+        );
+        assert_eq!(
+            search(MergeMode::SkipMerges).list_commit_hashes(),
+            [&changed_on_main, &changed_on_branch, &added]
+        );
+        assert_eq!(search(MergeMode::MergesOnly).list_commit_hashes(), [&merge]);
+
+        let parents = all
+            .iter()
+            .map(|commit| (commit.parents().to_vec(), commit.is_merge()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parents,
+            [
+                (vec![changed_on_main, changed_on_branch], true),
+                (vec![added.clone()], false),
+                (vec![added], false),
+                (vec![], false),
+            ]
+        );
+    }
+
+    #[test]
+    fn pickaxe() {
+        let file = FileFilterType::Relative("src/test_functions.rs".to_string());
+        let langs = function_grep::supported_languages::predefined_languages();
+        let search = |pickaxe, changes_only| {
+            get_function_history_with_options(
+                "empty_test",
+                &file,
+                &Filter::None,
+                langs,
+                &SearchOptions {
+                    pickaxe: Some(pickaxe),
+                    changes_only,
+                    ..SearchOptions::default()
+                },
+            )
+        };
+        let all = get_function_history("empty_test", &file, &Filter::None, langs).unwrap();
+        let oldest = *all.list_commit_hashes().last().unwrap();
+        // the function never changed, so only the oldest commit added the matches
+        for pickaxe in [
+            Pickaxe::Literal("println!(\"empty test\")".to_string()),
+            Pickaxe::Regex(r"String::from\(.*\)".to_string()),
+        ] {
+            assert_eq!(
+                search(pickaxe.clone(), false).unwrap().list_commit_hashes(),
+                [oldest]
+            );
+            let collapsed = search(pickaxe, true).unwrap();
+            assert_eq!(collapsed.list_commit_hashes(), [oldest]);
+            assert_eq!(
+                collapsed.get_commit().unwrap().unchanged_commits(),
+                all.commit_history.len() - 1
+            );
+        }
+        assert!(matches!(
+            search(Pickaxe::Literal("not in the function".to_string()), false),
+            Err(Error::NoHistory)
+        ));
+        assert!(matches!(
+            search(Pickaxe::Regex("(".to_string()), false),
+            Err(Error::InvalidFilter(_))
+        ));
+    }
+
+    #[test]
+    fn lifecycle_events() {
+        use types::{LifecycleEvent, LifecycleEventKind};
+        let fixture = Fixture::new();
+        fixture.write("src/a.rs", &handlers(1, 1));
+        let created = fixture.commit("add handler");
+        fixture.git(&["mv", "src/a.rs", "src/b.rs"]);
+        let moved = fixture.commit("move handler");
+        fixture.write("src/c.rs", &handlers(1, 1));
+        let duplicated = fixture.commit("copy handler");
+        fixture.git(&["rm", "--quiet", "src/b.rs", "src/c.rs"]);
+        let deleted = fixture.commit("delete handler");
+        fixture.write("src/b.rs", &handlers(2, 1));
+        let resurrected = fixture.commit("add handler back");
+
+        let history = fixture
+            .history("handler", &FileFilterType::None, &SearchOptions::default())
+            .unwrap();
+        let event = |commit: &String, kind| LifecycleEvent {
+            commit: commit.clone(),
+            kind,
+        };
+        assert_eq!(
+            history.events(),
+            [
+                event(&created, LifecycleEventKind::Created),
+                event(
+                    &moved,
+                    LifecycleEventKind::MovedFile {
+                        from: "src/a.rs".to_string(),
+                        to: "src/b.rs".to_string(),
+                    }
+                ),
+                event(
+                    &duplicated,
+                    LifecycleEventKind::Duplicated {
+                        into: "src/c.rs".to_string(),
+                    }
+                ),
+                event(&deleted, LifecycleEventKind::Deleted),
+                // it is back with a different body, and only in one of the files it was in before it was deleted
+                event(&resurrected, LifecycleEventKind::Resurrected),
+                event(&resurrected, LifecycleEventKind::Modified),
+            ]
+        );
+
+        // a move and a copy in the same commit, the file that sorts first is not the one that was moved
+        let fixture = Fixture::new();
+        fixture.write("src/a.rs", &handlers(1, 1));
+        fixture.write("src/x.rs", &handlers(2, 1));
+        fixture.commit("add handler");
+        fixture.git(&["mv", "src/a.rs", "src/m.rs"]);
+        fixture.write("src/0.rs", &handlers(2, 1));
+        let changed = fixture.commit("move and copy handler");
+        let history = fixture
+            .history("handler", &FileFilterType::None, &SearchOptions::default())
+            .unwrap();
+        assert_eq!(
+            history.events()[1..],
+            [
+                event(
+                    &changed,
+                    LifecycleEventKind::MovedFile {
+                        from: "src/a.rs".to_string(),
+                        to: "src/m.rs".to_string(),
+                    }
+                ),
+                event(
+                    &changed,
+                    LifecycleEventKind::Duplicated {
+                        into: "src/0.rs".to_string(),
+                    }
+                ),
+            ]
+        );
+
+        // the function is only created in the oldest commit if that commit added it
+        let fixture = Fixture::new();
+        fixture.write("README.md", "handlers\n");
+        fixture.commit("add readme");
+        fixture.write("src/a.rs", &handlers(1, 1));
+        let created = fixture.commit("add handler");
+        fixture.write("src/a.rs", &handlers(2, 1));
+        let modified = fixture.commit("change handler");
+        let events = |revision: Option<String>| {
+            fixture
+                .history(
+                    "handler",
+                    &FileFilterType::None,
+                    &SearchOptions {
+                        revision,
+                        ..SearchOptions::default()
+                    },
+                )
+                .unwrap()
+                .events()
+        };
+        assert_eq!(
+            events(None),
+            [
+                event(&created, LifecycleEventKind::Created),
+                event(&modified, LifecycleEventKind::Modified),
+            ]
+        );
+        assert_eq!(
+            events(Some(format!("{created}..HEAD"))),
+            [event(&modified, LifecycleEventKind::FirstSeen)]
+        );
+    }
+
+    #[test]
+    fn batch_search() {
+        let file = FileFilterType::Relative("src/test_functions.rs".to_string());
+        let langs = function_grep::supported_languages::predefined_languages();
+        let histories = get_function_histories(
+            &[
+                "empty_test",
+                "not_empty_test",
+                "empty_test",
+                "not_a_function",
+            ],
+            &file,
+            &Filter::None,
+            langs,
+            &SearchOptions::default(),
+        )
+        .unwrap();
+        // duplicate names are only searched once, and names without history are left out
+        assert_eq!(histories.len(), 2);
+        for name in ["empty_test", "not_empty_test"] {
+            let single = get_function_history(name, &file, &Filter::None, langs).unwrap();
+            let batch = &histories[name];
+            assert_eq!(batch.name, name);
+            assert_eq!(batch.list_commit_hashes(), single.list_commit_hashes());
+            for (batch, single) in batch.commit_history.iter().zip(&single.commit_history) {
+                assert!(batch.same_bodies(single));
+            }
+        }
+        assert!(matches!(
+            get_function_histories(
+                &["empty_test", ""],
+                &file,
+                &Filter::None,
+                langs,
+                &SearchOptions::default()
+            ),
+            Err(Error::EmptyName)
+        ));
+        assert!(matches!(
+            get_function_histories(
+                &["empty_test"],
+                &file,
+                &Filter::None,
+                langs,
+                &SearchOptions {
+                    follow_function_renames: true,
+                    ..SearchOptions::default()
+                }
+            ),
+            Err(Error::InvalidFilter(_))
+        ));
+    }
+
+    #[test]
+    fn parallelism() {
+        let file = FileFilterType::None;
+        let langs = function_grep::supported_languages::predefined_languages();
+        let all = get_function_history("empty_test", &file, &Filter::None, langs).unwrap();
+        let search = |parallelism| {
+            get_function_history_with_options(
+                "empty_test",
+                &file,
+                &Filter::None,
+                langs,
+                &SearchOptions {
+                    parallelism,
+                    ..SearchOptions::default()
+                },
+            )
+            .unwrap()
+        };
+        // how the work is spread over the threads does not change what is found
+        for strategy in [
+            ParallelStrategy::Auto,
+            ParallelStrategy::Sequential,
+            ParallelStrategy::Commits,
+            ParallelStrategy::Files,
+        ] {
+            for max_threads in [None, Some(1), Some(2)] {
+                let history = search(Parallelism {
+                    strategy,
+                    max_threads,
+                    ..Parallelism::default()
+                });
+                assert_eq!(history.list_commit_hashes(), all.list_commit_hashes());
+                assert_eq!(history.commit_history.len(), all.commit_history.len());
+                for (commit, other) in history.commit_history.iter().zip(&all.commit_history) {
+                    assert!(commit.same_bodies(other));
+                }
+            }
+        }
+        #[cfg(feature = "parallel")]
+        {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(2)
+                .build()
+                .unwrap();
+            let history = search(Parallelism {
+                strategy: ParallelStrategy::Files,
+                pool: Some(pool.into()),
+                ..Parallelism::default()
+            });
+            assert_eq!(history.list_commit_hashes(), all.list_commit_hashes());
+        }
+    }
+
+    #[test]
+    fn compact_files() {
+        let file = FileFilterType::Relative("src/test_functions.rs".to_string());
+        let langs = function_grep::supported_languages::predefined_languages();
+        let history = get_function_history_with_options(
+            "empty_test",
+            &file,
+            &Filter::None,
+            langs,
+            &SearchOptions {
+                parallelism: Parallelism {
+                    strategy: ParallelStrategy::Sequential,
+                    ..Parallelism::default()
+                },
+                ..SearchOptions::default()
+            },
+        )
+        .unwrap();
+        let newest = history.commit_history.first().unwrap().get_file().unwrap();
+        let oldest = history.commit_history.last().unwrap().get_file().unwrap();
+        assert!(newest
+            .result_texts()
+            .all(|text| text.contains("empty_test")));
+        // the whole file is read from the blob again when it is needed
+        let repo = gix::discover(".").unwrap();
+        let blob = |hash: &str, file: &ParsedFile| {
+            repo.rev_parse_single(format!("{hash}:{}", file.file_name().unwrap()).as_str())
+                .unwrap()
+                .object()
+                .unwrap()
+                .detach()
+        };
+        let hashes = history.list_commit_hashes();
+        let (newest_blob, oldest_blob) = (
+            blob(hashes[0], newest),
+            blob(hashes[hashes.len() - 1], oldest),
+        );
+        assert_eq!(
+            newest.file_text().unwrap(),
+            String::from_utf8_lossy(&newest_blob.data)
+        );
+        // and shared between the commits with the same blob
+        assert_eq!(
+            std::ptr::eq(newest.file_text().unwrap(), oldest.file_text().unwrap()),
+            newest_blob.id == oldest_blob.id
+        );
+        // filters that need the tree still work
+        let in_lines = function_grep::filter::Filters::default()
+            .get_filter("function_in_lines")
+            .unwrap()
+            .to_filter("0 100000")
+            .unwrap();
+        let filtered = history.filter_by(&Filter::PLFilter(in_lines)).unwrap();
+        assert_eq!(filtered.list_commit_hashes(), history.list_commit_hashes());
+    }
+
+    #[test]
+    fn navigation() {
+        let file = FileFilterType::Relative("src/test_functions.rs".to_string());
+        let langs = function_grep::supported_languages::predefined_languages();
+        let mut history = get_function_history("empty_test", &file, &Filter::None, langs).unwrap();
+        let hashes = history
+            .list_commit_hashes()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert!(hashes.len() > 1);
+        assert_eq!(history.len(), hashes.len());
+
+        // iterating does not loop forever, and does not move through the history
+        history.move_forward();
+        let iterated = (&history)
+            .into_iter()
+            .map(Commit::commit_hash)
+            .collect::<Vec<_>>();
+        assert_eq!(iterated, hashes);
+        assert_eq!(history.current_index(), 1);
+        assert_eq!(history.clone().count(), hashes.len());
+        assert_eq!(history[1].commit_hash(), hashes[1]);
+        assert!(history.get(hashes.len()).is_none());
+
+        assert!(history.go_to_last().is_some());
+        assert_eq!(history.current_index(), hashes.len() - 1);
+        assert!(history.move_forward().is_none());
+        assert!(history.go_to_first().is_some());
+        assert!(history.move_back().is_none());
+        assert!(history.go_to(hashes.len()).is_none());
+        assert_eq!(history.current_index(), 0);
+
+        // a prefix of the hash is enough, as long as only one commit starts with it
+        let last = &hashes[hashes.len() - 1];
+        history.go_to_commit(&last[..10].to_uppercase()).unwrap();
+        assert_eq!(history.get_commit().unwrap().commit_hash(), last);
+        assert!(matches!(
+            history.go_to_commit("not a hash"),
+            Err(Error::CommitNotFound(_))
+        ));
+        assert!(matches!(
+            history.go_to_commit(""),
+            Err(Error::AmbiguousCommit(_))
+        ));
+        assert_eq!(history.current_index(), hashes.len() - 1);
+
+        // the closest commit to a date, even when no commit was made at that time
+        let date = history[1].committer_date() + chrono::Duration::seconds(1);
+        history.go_to_date(&date.to_rfc3339()).unwrap();
+        assert_eq!(history.current_index(), 1);
+        history.go_to_date("1970-01-01").unwrap();
+        assert_eq!(history.current_index(), hashes.len() - 1);
+        assert!(matches!(
+            history.go_to_date("not a date"),
+            Err(Error::InvalidDate(..))
+        ));
+    }
+
+    #[test]
+    fn explicit_repo() {
+        let file = FileFilterType::Relative("src/test_functions.rs".to_string());
+        let langs = function_grep::supported_languages::predefined_languages();
+        let discovered = get_function_history("empty_test", &file, &Filter::None, langs).unwrap();
+        // any directory in the repository can be given
+        let options = SearchOptions {
+            repo: Some(PathBuf::from("src")),
+            ..SearchOptions::default()
+        };
+        let from_path =
+            get_function_history_with_options("empty_test", &file, &Filter::None, langs, &options)
+                .unwrap();
+        assert_eq!(
+            discovered.list_commit_hashes(),
+            from_path.list_commit_hashes()
+        );
+        let repo = gix::discover("src").unwrap();
+        let from_handle = get_function_history_in(
+            &repo,
+            "empty_test",
+            &file,
+            &Filter::None,
+            langs,
+            &SearchOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            discovered.list_commit_hashes(),
+            from_handle.list_commit_hashes()
+        );
+        assert_eq!(
+            get_git_info_in(&repo, None).unwrap().len(),
+            get_git_info_with_options(&options).unwrap().len()
+        );
+        let not_a_repo = SearchOptions {
+            repo: Some(std::env::temp_dir()),
+            ..SearchOptions::default()
+        };
+        assert!(get_git_info_with_options(&not_a_repo).is_err());
+    }
+
+    #[test]
+    fn similar_bodies() {
+        let body = "fn parse_args(args: &[String]) -> Config { Config::new(&args[1]) }";
+        assert!(
+            body_similarity(
+                body,
+                "fn parse_cli(args: &[String]) -> Config { Config::new(&args[1]) }"
+            ) >= RENAME_SIMILARITY
+        );
+        assert!(body_similarity(body, "fn other() { println!(\"hello\") }") < RENAME_SIMILARITY);
+    }
     //     #[test]
     //     fn test_date_range() {
     //         let now = Utc::now();
@@ -0,0 +1,292 @@
+//! How a search spreads its work over threads (with the `parallel` feature).
+//!
+//! Either the commits are searched in parallel (each commit on one thread),
+//! or the commits are searched one by one and the files of each commit are searched in parallel.
+//! By default the choice depends on how many commits there are to search,
+//! so a small search does not keep every core busy.
+
+use crate::Error;
+use gix::ObjectId;
+
+#[cfg(feature = "parallel")]
+use log::warn;
+#[cfg(feature = "parallel")]
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+#[cfg(feature = "parallel")]
+use std::{fmt, sync::Arc};
+
+/// How many commits there have to be for `ParallelStrategy::Auto` to search the commits in parallel,
+/// below that the overhead of the threads is not worth it.
+const PARALLEL_COMMITS: usize = 128;
+
+/// How many commits are searched (in parallel) at once, the found commits are passed on after each batch so they stay in the order they were walked.
+#[cfg(feature = "parallel")]
+const SEARCH_BATCH: usize = 64;
+
+/// How a search uses threads, see `SearchOptions::parallelism`.
+///
+/// Without the `parallel` feature everything is searched on the current thread, so this does nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Parallelism {
+    /// What is searched in parallel.
+    pub strategy: ParallelStrategy,
+    /// The most threads a search uses, by default this is the number of threads of the `pool`
+    /// (with `ParallelStrategy::Auto` a search of a few commits searches them on one thread).
+    pub max_threads: Option<usize>,
+    /// The thread pool to search on, instead of rayon's global thread pool.
+    /// With `max_threads` (or `ParallelStrategy::Auto`) the search can use fewer threads than the pool has,
+    /// it then gets its own smaller pool.
+    #[cfg(feature = "parallel")]
+    pub pool: Option<SearchPool>,
+}
+
+/// What is searched in parallel, see `Parallelism::strategy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParallelStrategy {
+    /// Decide based on how many commits there are to search:
+    /// the files of a single commit are searched in parallel,
+    /// a few commits are searched one by one,
+    /// and many commits (`128` or more) are searched in parallel.
+    #[default]
+    Auto,
+    /// Search everything on the current thread.
+    Sequential,
+    /// Search the commits in parallel.
+    Commits,
+    /// Search the commits one by one, but the files of each commit in parallel.
+    /// This works best when searching in many files (like with `FileFilterType::None`) over a few commits.
+    Files,
+}
+
+/// A rayon thread pool to search on, see `Parallelism::pool`.
+#[cfg(feature = "parallel")]
+#[derive(Clone)]
+pub struct SearchPool(Arc<rayon::ThreadPool>);
+
+#[cfg(feature = "parallel")]
+impl SearchPool {
+    pub const fn new(pool: Arc<rayon::ThreadPool>) -> Self {
+        Self(pool)
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl From<rayon::ThreadPool> for SearchPool {
+    fn from(pool: rayon::ThreadPool) -> Self {
+        Self::new(Arc::new(pool))
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl fmt::Debug for SearchPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SearchPool({} threads)", self.0.current_num_threads())
+    }
+}
+
+/// Pools are equal when they are clones of each other.
+#[cfg(feature = "parallel")]
+impl PartialEq for SearchPool {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl Eq for SearchPool {}
+
+/// The entries of a tree that have to be searched: their path, id and whether they are a (sub)tree.
+pub type TreeEntries = [(String, ObjectId, bool)];
+
+/// Where the work of one search runs, decided from `Parallelism` once it is known how many commits there are to search.
+///
+/// A search only makes one of these, so it starts at most one thread pool.
+pub struct Execution<'a> {
+    #[cfg(feature = "parallel")]
+    strategy: ParallelStrategy,
+    #[cfg(feature = "parallel")]
+    pool: Pool<'a>,
+    // the repository the files are searched in (on other threads) when searching files in parallel
+    #[cfg(feature = "parallel")]
+    repo: Option<&'a gix::ThreadSafeRepository>,
+    #[cfg(not(feature = "parallel"))]
+    pool: std::marker::PhantomData<&'a ()>,
+}
+
+#[cfg(feature = "parallel")]
+enum Pool<'a> {
+    Global,
+    Given(&'a rayon::ThreadPool),
+    Own(rayon::ThreadPool),
+}
+
+impl<'a> Execution<'a> {
+    /// Decides how to search `commits` commits in `repo`.
+    pub fn new(
+        parallelism: &'a Parallelism,
+        commits: usize,
+        repo: &'a gix::ThreadSafeRepository,
+    ) -> Self {
+        let strategy = match parallelism.strategy {
+            ParallelStrategy::Auto if commits <= 1 => ParallelStrategy::Files,
+            ParallelStrategy::Auto if commits < PARALLEL_COMMITS => ParallelStrategy::Sequential,
+            ParallelStrategy::Auto => ParallelStrategy::Commits,
+            strategy => strategy,
+        };
+        let threads = parallelism.max_threads;
+        if strategy == ParallelStrategy::Sequential || threads.is_some_and(|threads| threads <= 1) {
+            return Self::sequential();
+        }
+        Self::with_threads(parallelism, strategy, threads, repo)
+    }
+
+    /// How many of the commits `new` has to know about to decide how to search them,
+    /// with more commits than this it decides the same as with this many.
+    ///
+    /// This is kept small as none of the commits are searched until they are known.
+    #[cfg(feature = "parallel")]
+    pub fn lookahead(parallelism: &Parallelism) -> usize {
+        if parallelism.strategy == ParallelStrategy::Auto {
+            PARALLEL_COMMITS
+        } else {
+            0
+        }
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    pub const fn lookahead(_: &Parallelism) -> usize {
+        0
+    }
+
+    /// Searches everything on the current thread.
+    pub const fn sequential() -> Self {
+        Self {
+            #[cfg(feature = "parallel")]
+            strategy: ParallelStrategy::Sequential,
+            #[cfg(feature = "parallel")]
+            pool: Pool::Global,
+            #[cfg(feature = "parallel")]
+            repo: None,
+            #[cfg(not(feature = "parallel"))]
+            pool: std::marker::PhantomData,
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn with_threads(
+        parallelism: &'a Parallelism,
+        strategy: ParallelStrategy,
+        threads: Option<usize>,
+        repo: &'a gix::ThreadSafeRepository,
+    ) -> Self {
+        let pool = parallelism
+            .pool
+            .as_ref()
+            .map_or(Pool::Global, |pool| Pool::Given(&pool.0));
+        let available = match &pool {
+            Pool::Given(pool) => pool.current_num_threads(),
+            _ => rayon::current_num_threads(),
+        };
+        let pool = match threads {
+            Some(threads) if threads < available => rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .map_or_else(
+                    |e| {
+                        warn!("could not start a thread pool with {threads} threads: {e}");
+                        pool
+                    },
+                    Pool::Own,
+                ),
+            _ => pool,
+        };
+        Self {
+            strategy,
+            pool,
+            repo: Some(repo),
+        }
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    const fn with_threads(
+        _: &'a Parallelism,
+        _: ParallelStrategy,
+        _: Option<usize>,
+        _: &'a gix::ThreadSafeRepository,
+    ) -> Self {
+        Self::sequential()
+    }
+
+    /// Runs `op` on the thread pool of the search.
+    #[cfg(feature = "parallel")]
+    fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.pool {
+            Pool::Global => op(),
+            Pool::Given(pool) => pool.install(op),
+            Pool::Own(pool) => pool.install(op),
+        }
+    }
+
+    /// Searches each of the `commits` with `search` (as they are walked), and passes what was found to `found` (in the same order as the commits).
+    #[cfg_attr(not(feature = "parallel"), allow(clippy::unused_self))]
+    pub fn search_commits<T: Send + Sync, U: Send>(
+        &self,
+        commits: impl IntoIterator<Item = T>,
+        search: impl Fn(&T) -> Option<U> + Sync + Send,
+        mut found: impl FnMut(U),
+    ) {
+        let mut commits = commits.into_iter();
+        #[cfg(feature = "parallel")]
+        match self.strategy {
+            ParallelStrategy::Commits => loop {
+                let batch = commits.by_ref().take(SEARCH_BATCH).collect::<Vec<_>>();
+                if batch.is_empty() {
+                    break;
+                }
+                let batch =
+                    self.install(|| batch.par_iter().filter_map(&search).collect::<Vec<_>>());
+                batch.into_iter().for_each(&mut found);
+            },
+            ParallelStrategy::Files => {
+                for commit in commits {
+                    if let Some(searched) = self.install(|| search(&commit)) {
+                        found(searched);
+                    }
+                }
+            }
+            ParallelStrategy::Auto | ParallelStrategy::Sequential => {
+                commits.filter_map(|commit| search(&commit)).for_each(found);
+            }
+        }
+        #[cfg(not(feature = "parallel"))]
+        for searched in commits.by_ref().filter_map(|commit| search(&commit)) {
+            found(searched);
+        }
+    }
+
+    /// Searches each of the `entries` of a tree with `search` (in parallel when searching files in parallel),
+    /// and returns what was found in the same order as the entries.
+    ///
+    /// # Errors
+    ///
+    /// If searching any of the entries fails
+    #[cfg_attr(not(feature = "parallel"), allow(clippy::unused_self))]
+    pub fn search_entries<T: Send>(
+        &self,
+        repo: &gix::Repository,
+        entries: &TreeEntries,
+        search: impl Fn(&gix::Repository, &(String, ObjectId, bool)) -> Result<T, Error> + Sync + Send,
+    ) -> Result<Vec<T>, Error> {
+        #[cfg(feature = "parallel")]
+        if let (ParallelStrategy::Files, Some(th_repo)) = (self.strategy, self.repo) {
+            return entries
+                .par_iter()
+                .map_init(
+                    || th_repo.to_thread_local(),
+                    |repo, entry| search(repo, entry),
+                )
+                .collect();
+        }
+        entries.iter().map(|entry| search(repo, entry)).collect()
+    }
+}
@@ -0,0 +1,121 @@
+//! Matching the paths of the files in a commit against the file filters.
+//!
+//! Paths are always relative to the root of the repository and use `/` as the separator.
+
+use function_grep::supported_languages::SupportedLanguage;
+use gix::glob::wildmatch;
+use log::info;
+use regex::Regex;
+
+use crate::{ends_with_cmp_no_case, Error, FileFilterType, SearchOptions};
+
+/// Decides which files of a commit are searched:
+/// the files of a supported language that match the file filter and are not excluded.
+pub struct FileMatcher<'a> {
+    file_exts: Box<[&'static str]>,
+    exclude: &'a [String],
+    // `FileFilterType::Regex` is the same for the whole search, so it only gets compiled once
+    regex: Option<Regex>,
+}
+
+impl<'a> FileMatcher<'a> {
+    pub fn new(
+        langs: &[&dyn SupportedLanguage],
+        file: &FileFilterType,
+        options: &'a SearchOptions,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            file_exts: langs.iter().flat_map(|l| l.file_exts()).copied().collect(),
+            exclude: &options.exclude,
+            regex: match file {
+                FileFilterType::Regex(regex) => Some(compile_regex(regex)?),
+                _ => None,
+            },
+        })
+    }
+
+    /// checks if the files in the directory should be searched, as excluded directories are skipped
+    pub fn wanted_dir(&self, dir: &str) -> bool {
+        let excluded = self.excluded(dir);
+        if excluded {
+            info!("{dir} was skipped because it was excluded");
+        }
+        !excluded
+    }
+
+    /// checks if the file matches the file filter, is of a supported language, and is not excluded
+    pub fn wanted_file(&self, file: &str, filetype: &FileFilterType) -> bool {
+        let matches = match filetype {
+            FileFilterType::Relative(path) => ends_with_path(file, path),
+            FileFilterType::Absolute(path) => file == path,
+            FileFilterType::Directory(dir) => in_directory(file, dir),
+            FileFilterType::Glob(glob) => glob_matches(glob, file),
+            FileFilterType::Regex(_) => self.regex.as_ref().is_some_and(|re| re.is_match(file)),
+            FileFilterType::None => true,
+        };
+        if !matches {
+            info!("{file} was skipped because it did not match {filetype:?}");
+            return false;
+        }
+        if !self
+            .file_exts
+            .iter()
+            .any(|ext| ends_with_cmp_no_case(file, ext))
+        {
+            info!(
+                "{file} was skipped because it was not supported supported {:?}",
+                self.file_exts
+            );
+            return false;
+        }
+        if self.excluded(file) {
+            info!("{file} was skipped because it was excluded");
+            return false;
+        }
+        true
+    }
+
+    /// Patterns with a `/` have to match the whole path (like in a `.gitignore`),
+    /// other ones only have to match the name of the file or directory.
+    fn excluded(&self, path: &str) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.exclude
+            .iter()
+            .map(|pattern| pattern.trim_end_matches('/'))
+            .any(|pattern| {
+                if pattern.contains('/') {
+                    glob_matches(pattern.trim_start_matches('/'), path)
+                } else {
+                    glob_matches(pattern, name)
+                }
+            })
+    }
+}
+
+/// checks if `file` is `path` or ends with it, as whole path components (so `app.rs` does not match `src/myapp.rs`)
+pub fn ends_with_path(file: &str, path: &str) -> bool {
+    file.strip_suffix(path)
+        .is_some_and(|rest| rest.is_empty() || rest.ends_with('/'))
+}
+
+/// checks if `file` is somewhere in the directory `dir`, as whole path components (so `app` does not match `src/mapper.rs`)
+pub fn in_directory(file: &str, dir: &str) -> bool {
+    let dir = dir.trim_matches('/');
+    if dir.is_empty() {
+        return true;
+    }
+    format!("/{file}").contains(&format!("/{dir}/"))
+}
+
+/// checks if `path` matches the glob `pattern`, `*` does not match a `/` but `**` does
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    gix::glob::wildmatch(
+        pattern.into(),
+        path.into(),
+        wildmatch::Mode::NO_MATCH_SLASH_LITERAL,
+    )
+}
+
+pub fn compile_regex(regex: &str) -> Result<Regex, Error> {
+    Regex::new(regex).map_err(|e| Error::InvalidFilter(format!("invalid regex {regex}: {e}")))
+}
@@ -1,45 +1,52 @@
 use chrono::{DateTime, FixedOffset};
 use function_grep::ParsedFile;
+use gix::diff::blob::{diff, intern::InternedInput, Algorithm};
 #[cfg(feature = "parallel")]
 use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use regex::Regex;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::{self, Display, Formatter},
+    ops::{Index, Range},
 };
 
-use crate::Filter;
-
-#[derive(Debug, Clone)]
-pub enum ErrorReason {
-    NoHistory,
-    Other(String),
-}
+use crate::{
+    compiled::CompiledFilter,
+    dates::{parse_date, DateSemantics},
+    matches_person, paths, Error, Filter,
+};
 
-impl fmt::Display for ErrorReason {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::NoHistory => write!(f, "nothing found"),
-            Self::Other(other) => write!(f, "{other}"),
-        }
-    }
-}
+#[deprecated(note = "use `git_function_history::Error` instead")]
+pub type ErrorReason = Error;
 
 /// This holds information like date and commit `commit_hash` and also the list of function found in the commit.
 #[derive(Debug, Clone)]
 pub struct Commit {
     commit_hash: String,
     pub(crate) files: Vec<ParsedFile>,
+    // when it was committed
     pub(crate) date: DateTime<FixedOffset>,
     current_iter_pos: usize,
     current_pos: usize,
     author: String,
     email: String,
+    author_date: DateTime<FixedOffset>,
+    committer: String,
+    committer_email: String,
+    parents: Vec<String>,
     message: String,
+    tracked_path: Option<String>,
+    renamed_from: Option<String>,
+    unchanged_commits: usize,
+    deleted_in: Option<String>,
+    added: bool,
 }
 
 impl Commit {
     /// Create a new `Commit` with the given `commit_hash`, functions, and date.
     ///
+    /// The author is also taken to be the committer, and `date` to be when it was authored.
+    ///
     /// # Errors
     ///
     /// will return `Err` if it cannot parse the date provided.
@@ -50,17 +57,305 @@ impl Commit {
         author: &str,
         email: &str,
         message: &str,
-    ) -> Result<Self, String> {
-        Ok(Self {
+    ) -> Result<Self, Error> {
+        let date = DateTime::parse_from_rfc2822(date)
+            .map_err(|e| Error::InvalidDate(date.to_string(), e))?;
+        Ok(Self::new_at(
+            commit_hash,
+            files,
+            date,
+            author,
+            email,
+            message,
+        ))
+    }
+
+    /// Same as `Commit::new`, but with an already parsed date (which keeps its timezone).
+    pub(crate) fn new_at(
+        commit_hash: &str,
+        files: Vec<ParsedFile>,
+        date: DateTime<FixedOffset>,
+        author: &str,
+        email: &str,
+        message: &str,
+    ) -> Self {
+        Self {
             commit_hash: commit_hash.to_string(),
             files,
-            date: DateTime::parse_from_rfc2822(date).map_err(|e| e.to_string())?,
+            date,
             current_pos: 0,
             current_iter_pos: 0,
             author: author.to_string(),
             email: email.to_string(),
+            author_date: date,
+            committer: author.to_string(),
+            committer_email: email.to_string(),
+            parents: Vec::new(),
             message: message.to_string(),
-        })
+            tracked_path: None,
+            renamed_from: None,
+            unchanged_commits: 0,
+            deleted_in: None,
+            added: false,
+        }
+    }
+
+    /// sets who committed the commit, when it was authored by someone else or at another time (like when it was rebased)
+    pub(crate) fn with_committer(
+        mut self,
+        author_date: DateTime<FixedOffset>,
+        committer: &str,
+        committer_email: &str,
+    ) -> Self {
+        self.author_date = author_date;
+        self.committer = committer.to_string();
+        self.committer_email = committer_email.to_string();
+        self
+    }
+
+    /// returns the hash of the commit
+    pub fn commit_hash(&self) -> &str {
+        &self.commit_hash
+    }
+
+    /// returns the name of who authored the commit
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    /// returns the email of who authored the commit
+    pub fn author_email(&self) -> &str {
+        &self.email
+    }
+
+    /// returns when the commit was authored
+    pub const fn author_date(&self) -> DateTime<FixedOffset> {
+        self.author_date
+    }
+
+    /// returns the name of who committed the commit (who applied it, when rebasing, cherry-picking or applying a patch)
+    pub fn committer(&self) -> &str {
+        &self.committer
+    }
+
+    /// returns the email of who committed the commit
+    pub fn committer_email(&self) -> &str {
+        &self.committer_email
+    }
+
+    /// returns when the commit was committed, which is the date the commits are ordered and filtered by
+    pub const fn committer_date(&self) -> DateTime<FixedOffset> {
+        self.date
+    }
+
+    pub(crate) fn with_parents(mut self, parents: Vec<String>) -> Self {
+        self.parents = parents;
+        self
+    }
+
+    /// returns the hashes of the parents of the commit, the first one is the commit it was made on top of,
+    /// and the other ones are the commits that got merged into it
+    pub fn parents(&self) -> &[String] {
+        &self.parents
+    }
+
+    /// checks if the commit merged other commits (so it has more than one parent)
+    pub const fn is_merge(&self) -> bool {
+        self.parents.len() > 1
+    }
+
+    /// sets the path that the file was at in this commit (when following renames)
+    pub(crate) fn with_tracked_path(mut self, tracked_path: Option<String>) -> Self {
+        self.tracked_path = tracked_path;
+        self
+    }
+
+    /// returns the path that the followed file had in this commit
+    ///
+    /// this is only set when searching with `SearchOptions::follow`
+    pub fn tracked_path(&self) -> Option<&str> {
+        self.tracked_path.as_deref()
+    }
+
+    pub(crate) fn set_renamed_from(&mut self, old_name: String) {
+        self.renamed_from = Some(old_name);
+    }
+
+    /// returns the name the function had before this commit renamed it
+    ///
+    /// this is only set when searching with `SearchOptions::follow_function_renames`
+    pub fn renamed_from(&self) -> Option<&str> {
+        self.renamed_from.as_deref()
+    }
+
+    pub(crate) const fn with_unchanged_commits(mut self, unchanged_commits: usize) -> Self {
+        self.unchanged_commits = unchanged_commits;
+        self
+    }
+
+    /// returns how many (newer) commits this commit stands for, in which the function did not change
+    ///
+    /// this is only set when searching with `SearchOptions::changes_only`
+    pub const fn unchanged_commits(&self) -> usize {
+        self.unchanged_commits
+    }
+
+    pub(crate) fn with_deleted_in(mut self, deleted_in: Option<String>) -> Self {
+        self.deleted_in = deleted_in;
+        self
+    }
+
+    /// returns the hash of the (newer) commit that deleted the function after this commit,
+    /// if the function was missing from the next commits that were searched
+    pub fn deleted_in(&self) -> Option<&str> {
+        self.deleted_in.as_deref()
+    }
+
+    pub(crate) const fn set_added(&mut self) {
+        self.added = true;
+    }
+
+    /// returns if the function was added in this commit, which is only known when one of its parents was searched and did not have the function
+    pub const fn added(&self) -> bool {
+        self.added
+    }
+
+    /// returns what happened to the function between `older` and this commit (not counting deletions)
+    fn lifecycle_changes(&self, older: &Self) -> Vec<LifecycleEventKind> {
+        fn bodies<'a>(commit: &'a Commit, name: &str) -> Vec<&'a str> {
+            commit
+                .files
+                .iter()
+                .filter(|file| file.file_name() == Some(name))
+                .flat_map(ParsedFile::result_texts)
+                .collect()
+        }
+        let names = |commit: &Self| {
+            commit
+                .files
+                .iter()
+                .filter_map(ParsedFile::file_name)
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let (old_names, new_names) = (names(older), names(self));
+        let mut removed = old_names
+            .iter()
+            .filter(|name| !new_names.contains(name))
+            .collect::<Vec<_>>();
+        let added = new_names
+            .iter()
+            .filter(|name| !old_names.contains(name))
+            .collect::<Vec<_>>();
+        // an added file the function is the same in as in a removed file is where that file was moved to
+        let mut moved = vec![];
+        let mut unpaired = vec![];
+        for to in added {
+            let body = bodies(self, to);
+            match removed.iter().position(|from| bodies(older, from) == body) {
+                Some(from) => moved.push((removed.remove(from), to)),
+                None => unpaired.push(to),
+            }
+        }
+        // the rest are copies of a file the function was already in, or moves where the function changed as well
+        let mut duplicated = vec![];
+        for to in unpaired {
+            let body = bodies(self, to);
+            let copy = old_names.iter().any(|name| bodies(older, name) == body);
+            if copy || removed.is_empty() {
+                duplicated.push(to);
+            } else {
+                moved.push((removed.remove(0), to));
+            }
+        }
+        // the function was removed from some of its files, or its body changed in the same or the moved file
+        let modified = !removed.is_empty()
+            || old_names
+                .iter()
+                .filter(|name| new_names.contains(name))
+                .map(|name| (name, name))
+                .chain(moved.iter().copied())
+                .any(|(from, to)| bodies(older, from) != bodies(self, to));
+        let mut changes = moved
+            .into_iter()
+            .map(|(from, to)| LifecycleEventKind::MovedFile {
+                from: from.clone(),
+                to: to.clone(),
+            })
+            .collect::<Vec<_>>();
+        changes.extend(
+            duplicated
+                .into_iter()
+                .map(|into| LifecycleEventKind::Duplicated { into: into.clone() }),
+        );
+        if modified {
+            changes.push(LifecycleEventKind::Modified);
+        }
+        changes
+    }
+
+    /// checks if the function bodies in both commits are the same
+    pub(crate) fn same_bodies(&self, other: &Self) -> bool {
+        self.files
+            .iter()
+            .flat_map(ParsedFile::result_texts)
+            .eq(other.files.iter().flat_map(ParsedFile::result_texts))
+    }
+
+    /// counts how many times `regex` matches in all the function bodies in this commit
+    pub(crate) fn count_matches(&self, regex: &Regex) -> usize {
+        self.files
+            .iter()
+            .flat_map(ParsedFile::result_texts)
+            .map(|body| regex.find_iter(body).count())
+            .sum()
+    }
+
+    /// returns the lines of all the function bodies in this commit, with their line number (in their file)
+    fn body_lines(&self, path: &str) -> Vec<Vec<(usize, &str)>> {
+        self.files
+            .iter()
+            .filter(|file| file.file_name() == Some(path))
+            .flat_map(|file| file.results().iter().zip(file.result_texts()))
+            .map(|(range, body)| {
+                body.lines()
+                    .enumerate()
+                    .map(|(line, text)| (range.start_point.row + line + 1, text))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// returns what changed in the function between `older` and this commit
+    ///
+    /// each version of the function is compared with the one in the same file (the first with the first one in that file and so on),
+    /// if `older` is `None` every line of the function is added
+    pub fn diff(&self, older: Option<&Self>) -> FunctionDiff {
+        let mut paths = self
+            .files
+            .iter()
+            .chain(older.into_iter().flat_map(|older| &older.files))
+            .filter_map(ParsedFile::file_name)
+            .collect::<Vec<_>>();
+        let mut seen = HashSet::new();
+        paths.retain(|path| seen.insert(*path));
+        let mut hunks = vec![];
+        for path in paths {
+            let old = older
+                .map(|older| older.body_lines(path))
+                .unwrap_or_default();
+            let new = self.body_lines(path);
+            for function in 0..old.len().max(new.len()) {
+                let old_lines = old.get(function).map_or(&[][..], Vec::as_slice);
+                let new_lines = new.get(function).map_or(&[][..], Vec::as_slice);
+                hunks.extend(diff_lines(path, old_lines, new_lines));
+            }
+        }
+        FunctionDiff {
+            old_commit: older.map(|commit| commit.commit_hash.clone()),
+            new_commit: self.commit_hash.clone(),
+            hunks,
+        }
     }
 
     /// sets the current file to the next file if possible
@@ -80,15 +375,43 @@ impl Commit {
     }
 
     /// returns a hashmap containing the commits metadata
-    /// inlcuding the `commit hash`, `date`, and `file`
+    /// inlcuding the `commit hash`, `date` (when it was committed), `author`, and `file`
+    ///
+    /// the `author date` and `committer` are only included when they are not the `date` and `author`,
+    /// and the `parents` only when it has any
     pub fn get_metadata(&self) -> HashMap<String, String> {
         let mut map = HashMap::new();
         map.insert("commit hash".to_string(), self.commit_hash.clone());
         map.insert("date".to_string(), self.date.to_rfc2822());
+        map.insert(
+            "author".to_string(),
+            format!("{} <{}>", self.author, self.email),
+        );
+        if !self.parents.is_empty() {
+            map.insert("parents".to_string(), self.parents.join(" "));
+        }
+        if self.author_date != self.date {
+            map.insert("author date".to_string(), self.author_date.to_rfc2822());
+        }
+        if (&self.committer, &self.committer_email) != (&self.author, &self.email) {
+            map.insert(
+                "committer".to_string(),
+                format!("{} <{}>", self.committer, self.committer_email),
+            );
+        }
         map.insert(
             "file".to_string(),
             self.files.get(self.current_pos).map_or_else(||"error occured, could not get filename, no file found\nfile a bug to https://github.com/mendelsshop/git_function_history/issues".to_string(), |file|file.file_name().expect("error ocurred, could not get filename, no filename for current file\nfile a bug to https://github.com/mendelsshop/git_function_history/issues").to_string()),
         );
+        if let Some(old_name) = &self.renamed_from {
+            map.insert("renamed from".to_string(), old_name.clone());
+        }
+        if self.unchanged_commits > 0 {
+            map.insert(
+                "unchanged commits".to_string(),
+                self.unchanged_commits.to_string(),
+            );
+        }
         map
     }
 
@@ -114,62 +437,44 @@ impl Commit {
 
     /// returns a new `Commit` by filtering the current one by the filter specified (does not modify the current one).
     ///
-    /// valid filters are: `Filter::Language`, `Filter::PLFilter`,  `Filter::FileAbsolute`, `Filter::FileRelative`, `Filter::None`, and `Filter::Directory`.
+    /// valid filters are: `Filter::Language`, `Filter::PLFilter`,  `Filter::FileAbsolute`, `Filter::FileRelative`, `Filter::FileGlob`, `Filter::FileRegex`, `Filter::None`, and `Filter::Directory`,
+    /// and `Filter::And`, `Filter::Or`, `Filter::Not` and `Filter::LocalTime` of any filters (filters on the commit itself like `Filter::Author` either keep or remove all of its files).
     ///
     /// # Errors
     ///
     /// Will result in an `Err` if a non-valid filter is given, or if no results are found for the given filter
-    pub fn filter_by(&self, filter: &Filter) -> Result<Self, ErrorReason> {
+    pub fn filter_by(&self, filter: &Filter) -> Result<Self, Error> {
         match filter {
             Filter::FileAbsolute(_)
             | Filter::FileRelative(_)
             | Filter::Directory(_)
+            | Filter::FileGlob(_)
+            | Filter::FileRegex(_)
             | Filter::PLFilter(_)
             | Filter::Language(_)
-            | Filter::None => {}
-            _ => return Err(ErrorReason::Other(format!("Invalid filter {filter:?}"))),
+            | Filter::None
+            | Filter::And(_)
+            | Filter::Or(_)
+            | Filter::Not(_)
+            | Filter::LocalTime(_) => {}
+            _ => {
+                return Err(Error::InvalidFilter(format!(
+                    "{filter:?} cannot be used to filter a commit"
+                )))
+            }
         }
-        #[cfg(feature = "parallel")]
-        let t = self.files.iter();
-        #[cfg(not(feature = "parallel"))]
-        let t = self.files.iter();
-        let vec: Vec<_> = t
-            .filter_map(|f| match filter {
-                Filter::FileAbsolute(file) => {
-                    if f.file_name()? == *file {
-                        Some(f.clone())
-                    } else {
-                        None
-                    }
-                }
-                Filter::FileRelative(file) => {
-                    if f.file_name()?.ends_with(file) {
-                        Some(f.clone())
-                    } else {
-                        None
-                    }
-                }
-                Filter::Directory(dir) => {
-                    if f.file_name()?.contains(dir) {
-                        Some(f.clone())
-                    } else {
-                        None
-                    }
-                }
-                Filter::Language(lang) => {
-                    if f.language() == *lang {
-                        Some(f.clone())
-                    } else {
-                        None
-                    }
-                }
-                Filter::PLFilter(filter) => f.filter(filter).ok(),
-                _ => None,
-            })
-            .collect();
+        self.filter_compiled(&CompiledFilter::new(filter)?)
+    }
 
+    /// same as `filter_by`, but with a filter that was already compiled (and checked to be valid for a commit)
+    pub(crate) fn filter_compiled(&self, filter: &CompiledFilter<'_>) -> Result<Self, Error> {
+        let vec: Vec<_> = self
+            .files
+            .iter()
+            .filter_map(|f| self.filter_file(f, filter, DateSemantics::Utc))
+            .collect();
         if vec.is_empty() {
-            return Err(ErrorReason::Other("No files found for filter".to_string()))?;
+            return Err(Error::NoHistory);
         }
         Ok(Self {
             commit_hash: self.commit_hash.clone(),
@@ -179,11 +484,91 @@ impl Commit {
             current_iter_pos: 0,
             author: self.author.clone(),
             email: self.email.clone(),
+            author_date: self.author_date,
+            committer: self.committer.clone(),
+            committer_email: self.committer_email.clone(),
+            parents: self.parents.clone(),
             message: self.message.clone(),
+            tracked_path: self.tracked_path.clone(),
+            renamed_from: self.renamed_from.clone(),
+            unchanged_commits: self.unchanged_commits,
+            deleted_in: self.deleted_in.clone(),
+            added: self.added,
         })
     }
 }
 
+impl Commit {
+    /// returns what is left of the file `f` (of this commit) after filtering it by `filter`
+    ///
+    /// in a `Filter::Or` the file is kept as the first filter that keeps it
+    fn filter_file(
+        &self,
+        f: &ParsedFile,
+        filter: &CompiledFilter<'_>,
+        dates: DateSemantics,
+    ) -> Option<ParsedFile> {
+        let keep = match filter {
+            CompiledFilter::Other(Filter::FileAbsolute(file)) => f.file_name()? == *file,
+            CompiledFilter::Other(Filter::FileRelative(file)) => {
+                paths::ends_with_path(f.file_name()?, file)
+            }
+            CompiledFilter::Other(Filter::Directory(dir)) => {
+                paths::in_directory(f.file_name()?, dir)
+            }
+            CompiledFilter::Other(Filter::FileGlob(glob)) => {
+                paths::glob_matches(glob, f.file_name()?)
+            }
+            CompiledFilter::FileRegex(regex) => regex.is_match(f.file_name()?),
+            CompiledFilter::Other(Filter::Language(lang)) => f.language() == *lang,
+            CompiledFilter::Other(Filter::PLFilter(filter)) => return f.filter(filter).ok(),
+            CompiledFilter::And(filters) => {
+                return filters
+                    .iter()
+                    .try_fold(f.clone(), |f, filter| self.filter_file(&f, filter, dates))
+            }
+            CompiledFilter::Or(filters) => {
+                return filters
+                    .iter()
+                    .find_map(|filter| self.filter_file(f, filter, dates))
+            }
+            CompiledFilter::Not(filter) => self.filter_file(f, filter, dates).is_none(),
+            CompiledFilter::LocalTime(filter) => {
+                return self.filter_file(f, filter, DateSemantics::Local)
+            }
+            CompiledFilter::Other(Filter::None) => true,
+            metadata => self.matches(metadata, dates),
+        };
+        keep.then(|| f.clone())
+    }
+
+    /// checks if the commit itself (not its files) matches `filter`
+    fn matches(&self, filter: &CompiledFilter<'_>, dates: DateSemantics) -> bool {
+        match filter {
+            CompiledFilter::Date(date) => dates.key(*date) == dates.key(self.date),
+            CompiledFilter::DateRange(range) => range.contains(self.date, dates),
+            CompiledFilter::AuthorDateRange(range) => range.contains(self.author_date, dates),
+            CompiledFilter::Other(Filter::CommitHash(commit_hash)) => {
+                &self.commit_hash == commit_hash
+            }
+            CompiledFilter::AuthorRegex(_)
+            | CompiledFilter::Other(
+                Filter::Author(_)
+                | Filter::AuthorEmail(_)
+                | Filter::Committer(_)
+                | Filter::CommitterEmail(_),
+            ) => matches_person(
+                filter,
+                (&self.author, &self.email),
+                (&self.committer, &self.committer_email),
+            ),
+            CompiledFilter::Other(Filter::Message(message)) => self.message.contains(message),
+            // the other filters are about the files, not the commit
+            _ => true,
+        }
+    }
+}
+
 impl Iterator for Commit {
     type Item = ParsedFile;
     fn next(&mut self) -> Option<Self::Item> {
@@ -227,6 +612,12 @@ impl FunctionHistory {
             current_pos: 0,
         }
     }
+    /// adds older commits to the end of the history (like the ones a search found since the history was made),
+    /// the current commit stays the same
+    pub fn extend(&mut self, commits: impl IntoIterator<Item = Commit>) {
+        self.commit_history.extend(commits);
+    }
+
     /// This will return a vector of all the commit hashess in the history.
     pub fn list_commit_hashes(&self) -> Vec<&str> {
         self.commit_history
@@ -237,28 +628,110 @@ impl FunctionHistory {
 
     /// this will move to the next commit if possible
     pub fn move_forward(&mut self) -> Option<()> {
-        if self.current_pos >= self.commit_history.len() - 1 {
-            return None;
-        }
-        self.current_pos += 1;
-        self.commit_history
-            .get_mut(self.current_pos)?
-            .current_iter_pos = 0;
-        self.commit_history.get_mut(self.current_pos)?.current_pos = 0;
-        Some(())
+        self.go_to(self.current_pos + 1)
     }
 
     /// this will move to the previous commit if possible
     pub fn move_back(&mut self) -> Option<()> {
-        if self.current_pos == 0 {
-            return None;
+        self.go_to(self.current_pos.checked_sub(1)?)
+    }
+
+    /// this will move to the commit at `index` in the history (the newest commit is at 0), starting at its first file
+    ///
+    /// returns `None` (and stays at the current commit) if `index` is out of bounds
+    pub fn go_to(&mut self, index: usize) -> Option<()> {
+        let commit = self.commit_history.get_mut(index)?;
+        commit.current_iter_pos = 0;
+        commit.current_pos = 0;
+        self.current_pos = index;
+        Some(())
+    }
+
+    /// this will move to the newest commit, returns `None` if the history is empty
+    pub fn go_to_first(&mut self) -> Option<()> {
+        self.go_to(0)
+    }
+
+    /// this will move to the oldest commit, returns `None` if the history is empty
+    pub fn go_to_last(&mut self) -> Option<()> {
+        self.go_to(self.commit_history.len().checked_sub(1)?)
+    }
+
+    /// this will move to the commit whose hash starts with `hash` (like `git` a few characters of the hash are enough)
+    ///
+    /// # Errors
+    ///
+    /// returns `Err` if no commit or more than one commit in the history starts with `hash`
+    pub fn go_to_commit(&mut self, hash: &str) -> Result<(), Error> {
+        let index = self.position_of_commit(hash)?;
+        self.go_to(index).ok_or(Error::NoHistory)
+    }
+
+    /// this will move to the commit committed closest to `date`, which can be in any format the date filters accept (see `Filter::Date`)
+    ///
+    /// # Errors
+    ///
+    /// returns `Err` if the date cannot be parsed or the history is empty
+    pub fn go_to_date(&mut self, date: &str) -> Result<(), Error> {
+        let index = self
+            .position_of_date(parse_date(date)?)
+            .ok_or(Error::NoHistory)?;
+        self.go_to(index).ok_or(Error::NoHistory)
+    }
+
+    /// returns the position in the history of the commit whose hash starts with `hash`
+    ///
+    /// # Errors
+    ///
+    /// returns `Err` if no commit or more than one commit in the history starts with `hash`
+    pub fn position_of_commit(&self, hash: &str) -> Result<usize, Error> {
+        let hash = hash.trim().to_lowercase();
+        let mut found = self
+            .commit_history
+            .iter()
+            .enumerate()
+            .filter(|(_, commit)| commit.commit_hash.starts_with(&hash))
+            .map(|(index, _)| index);
+        match (found.next(), found.next()) {
+            (Some(index), None) if !hash.is_empty() => Ok(index),
+            (Some(_), _) => Err(Error::AmbiguousCommit(hash)),
+            (None, _) => Err(Error::CommitNotFound(hash)),
         }
-        self.current_pos -= 1;
+    }
+
+    /// returns the position in the history of the commit committed closest to `date` (the newer one if two are as close),
+    /// or `None` if the history is empty
+    pub fn position_of_date(&self, date: DateTime<FixedOffset>) -> Option<usize> {
         self.commit_history
-            .get_mut(self.current_pos)?
-            .current_iter_pos = 0;
-        self.commit_history.get_mut(self.current_pos)?.current_pos = 0;
-        Some(())
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, commit)| DateSemantics::Utc.distance(commit.committer_date(), date))
+            .map(|(index, _)| index)
+    }
+
+    /// returns the position of the current commit in the history (the newest commit is at 0)
+    pub const fn current_index(&self) -> usize {
+        self.current_pos
+    }
+
+    /// returns the commit at `index` in the history (the newest commit is at 0), or `None` if `index` is out of bounds
+    pub fn get(&self, index: usize) -> Option<&Commit> {
+        self.commit_history.get(index)
+    }
+
+    /// returns how many commits are in the history
+    pub const fn len(&self) -> usize {
+        self.commit_history.len()
+    }
+
+    /// returns `true` if there are no commits in the history
+    pub const fn is_empty(&self) -> bool {
+        self.commit_history.is_empty()
+    }
+
+    /// returns an iterator over the commits in the history (newest first), without changing the current commit
+    pub fn iter(&self) -> std::slice::Iter<'_, Commit> {
+        self.commit_history.iter()
     }
 
     /// this will move to the next file in the current commit if possible
@@ -296,7 +769,7 @@ impl FunctionHistory {
     /// returns the directions in which ways you can move through the commit history
     pub const fn get_move_direction(&self) -> Directions {
         match self.current_pos {
-            0 if self.commit_history.len() == 1 => Directions::None,
+            _ if self.commit_history.len() <= 1 => Directions::None,
             0 => Directions::Forward,
             x if x == self.commit_history.len() - 1 => Directions::Back,
             _ => Directions::Both,
@@ -310,7 +783,7 @@ impl FunctionHistory {
             .map_or(Directions::None, Commit::get_move_direction)
     }
     /// returns a new `FunctionHistory` by filtering the current one by the filter specified (does not modify the current one).
-    /// All filter are valid
+    /// All filter are valid, and can be combined with `Filter::And`, `Filter::Or` and `Filter::Not`
     ///
     /// # examples
     /// ```rust
@@ -324,73 +797,32 @@ impl FunctionHistory {
     /// # Errors
     ///
     /// returns `Err` if no files or commits are match the filter specified
-    pub fn filter_by(&self, filter: &Filter) -> Result<Self, ErrorReason> {
+    pub fn filter_by(&self, filter: &Filter) -> Result<Self, Error> {
+        let compiled = &CompiledFilter::new(filter)?;
         #[cfg(feature = "parallel")]
         let t = self.commit_history.par_iter();
         #[cfg(not(feature = "parallel"))]
         let t = self.commit_history.iter();
         let vec: Vec<Commit> = t
             .filter_map(|f| match filter {
-                Filter::PLFilter(_)
-                | Filter::Directory(_)
-                | Filter::FileAbsolute(_)
-                | Filter::FileRelative(_)
-                | Filter::Language(_) => f.filter_by(filter).ok(),
-                Filter::CommitHash(commit_hash) => {
-                    if &f.commit_hash == commit_hash {
-                        Some(f.clone())
-                    } else {
-                        None
-                    }
-                }
-
-                Filter::Date(date) => {
-                    if &f.date.to_rfc2822() == date {
-                        Some(f.clone())
-                    } else {
-                        None
-                    }
-                }
-                Filter::DateRange(start, end) => {
-                    let Ok(start) = DateTime::parse_from_rfc2822(start) else {
-                        return None;
-                    };
-                    let Ok(end) = DateTime::parse_from_rfc2822(end) else {
-                        return None;
-                    };
-                    if f.date >= start || f.date <= end {
-                        Some(f.clone())
-                    } else {
-                        None
-                    }
-                }
-                Filter::Author(author) => {
-                    if &f.author == author {
-                        Some(f.clone())
-                    } else {
-                        None
-                    }
-                }
-                Filter::AuthorEmail(email) => {
-                    if &f.email == email {
-                        Some(f.clone())
-                    } else {
-                        None
-                    }
-                }
-                Filter::Message(message) => {
-                    if f.message.contains(message) {
-                        Some(f.clone())
-                    } else {
-                        None
-                    }
-                }
                 Filter::None => None,
+                // filters on the commit itself keep the whole commit
+                Filter::CommitHash(_)
+                | Filter::Date(_)
+                | Filter::DateRange(_, _)
+                | Filter::AuthorDateRange(_, _)
+                | Filter::Author(_)
+                | Filter::AuthorEmail(_)
+                | Filter::AuthorRegex(_)
+                | Filter::Committer(_)
+                | Filter::CommitterEmail(_)
+                | Filter::Message(_) => f.matches(compiled, DateSemantics::Utc).then(|| f.clone()),
+                _ => f.filter_compiled(compiled).ok(),
             })
             .collect();
 
         if vec.is_empty() {
-            return Err(ErrorReason::NoHistory);
+            return Err(Error::NoHistory);
         }
         Ok(Self {
             commit_history: vec,
@@ -401,6 +833,71 @@ impl FunctionHistory {
     }
 }
 
+impl FunctionHistory {
+    /// returns what changed in the function between the commits at the positions `older` and `newer` in the history (the newest commit is at 0)
+    ///
+    /// returns `None` if either position is out of bounds
+    pub fn diff(&self, older: usize, newer: usize) -> Option<FunctionDiff> {
+        let older = self.commit_history.get(older)?;
+        Some(self.commit_history.get(newer)?.diff(Some(older)))
+    }
+
+    /// returns what changed in the function in the current commit, compared to the commit before it
+    ///
+    /// for the oldest commit every line of the function is added
+    pub fn diff_with_previous(&self) -> Option<FunctionDiff> {
+        let commit = self.commit_history.get(self.current_pos)?;
+        Some(commit.diff(self.commit_history.get(self.current_pos + 1)))
+    }
+
+    /// returns what happened to the function over the history (oldest first), by comparing each commit with the commit before it
+    ///
+    /// the function counts as deleted when it was missing from the commits that were searched after it (see `Commit::deleted_in`),
+    /// so commits that were left out (by a filter or `SearchOptions::merges`) do not count as deleting it
+    ///
+    /// the oldest commit only counts as creating the function if it has no parents or if it added the function (see `Commit::added`),
+    /// otherwise the function may be older than the history (like when it only goes back to a tag), and it is just first seen there
+    pub fn events(&self) -> Vec<LifecycleEvent> {
+        let mut events = vec![];
+        let mut older: Option<&Commit> = None;
+        for commit in self.commit_history.iter().rev() {
+            let event = |kind| LifecycleEvent {
+                commit: commit.commit_hash.clone(),
+                kind,
+            };
+            match older {
+                None if commit.parents.is_empty() || commit.added => {
+                    events.push(event(LifecycleEventKind::Created));
+                }
+                None => events.push(event(LifecycleEventKind::FirstSeen)),
+                Some(older) => {
+                    if older.deleted_in.is_some() {
+                        events.push(event(LifecycleEventKind::Resurrected));
+                    }
+                    events.extend(commit.lifecycle_changes(older).into_iter().map(event));
+                }
+            }
+            if let Some(deleted_in) = &commit.deleted_in {
+                events.push(LifecycleEvent {
+                    commit: deleted_in.clone(),
+                    kind: LifecycleEventKind::Deleted,
+                });
+            }
+            older = Some(commit);
+        }
+        events
+    }
+
+    /// returns what changed in the function in each commit compared to the commit before it (newest first)
+    pub fn diffs(&self) -> Vec<FunctionDiff> {
+        self.commit_history
+            .iter()
+            .enumerate()
+            .map(|(pos, commit)| commit.diff(self.commit_history.get(pos + 1)))
+            .collect()
+    }
+}
+
 // TODO: fix this documentaton (and maybe the whole macro)
 
 /// Macro to filter a the whole git history, a singe commit, or a file.
@@ -445,10 +942,28 @@ impl Display for FunctionHistory {
     }
 }
 
+/// Gives back a copy of each commit (newest first), to go over the commits without copying them use `FunctionHistory::iter` (or a `for` loop over `&history`).
 impl Iterator for FunctionHistory {
     type Item = Commit;
     fn next(&mut self) -> Option<Self::Item> {
-        self.commit_history.get(self.current_iter_pos).cloned()
+        let commit = self.commit_history.get(self.current_iter_pos).cloned();
+        self.current_iter_pos += 1;
+        commit
+    }
+}
+
+impl<'a> IntoIterator for &'a FunctionHistory {
+    type Item = &'a Commit;
+    type IntoIter = std::slice::Iter<'a, Commit>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Index<usize> for FunctionHistory {
+    type Output = Commit;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.commit_history[index]
     }
 }
 
@@ -464,3 +979,249 @@ pub enum Directions {
     /// You can move in both directions
     Both,
 }
+
+/// Something that happened to the function in a commit, see `FunctionHistory::events`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LifecycleEvent {
+    /// the hash of the commit it happened in
+    pub commit: String,
+    pub kind: LifecycleEventKind,
+}
+
+impl Display for LifecycleEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.commit, self.kind)
+    }
+}
+
+/// What happened to the function in a `LifecycleEvent`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleEventKind {
+    /// the function was added (in the oldest commit it was found in)
+    Created,
+    /// the function was in the oldest commit of the history, but it may have been added before it
+    FirstSeen,
+    /// the body of the function changed (or it was removed from some of the files it was in)
+    Modified,
+    /// the function moved from one file to another
+    MovedFile { from: String, to: String },
+    /// the function was copied into another file, and is in both of them now
+    Duplicated { into: String },
+    /// the function was deleted, the commit is not in the history as the function is not in it
+    Deleted,
+    /// the function was added again after it was deleted
+    Resurrected,
+}
+
+impl Display for LifecycleEventKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Created => write!(f, "created"),
+            Self::FirstSeen => write!(f, "first seen"),
+            Self::Modified => write!(f, "modified"),
+            Self::MovedFile { from, to } => write!(f, "moved from {from} to {to}"),
+            Self::Duplicated { into } => write!(f, "duplicated into {into}"),
+            Self::Deleted => write!(f, "deleted"),
+            Self::Resurrected => write!(f, "resurrected"),
+        }
+    }
+}
+
+/// How many unchanged lines are shown around each change in a `Hunk`
+const DIFF_CONTEXT: usize = 3;
+
+/// What changed in a function between two commits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionDiff {
+    /// the commit hash of the older version, `None` if there was no older version
+    pub old_commit: Option<String>,
+    /// the commit hash of the newer version
+    pub new_commit: String,
+    /// the changed parts of the function, the hunks of each file are next to each other
+    pub hunks: Vec<Hunk>,
+}
+
+impl FunctionDiff {
+    /// returns how many lines were added
+    pub fn added(&self) -> usize {
+        self.lines()
+            .filter(|line| matches!(line, DiffLine::Added { .. }))
+            .count()
+    }
+
+    /// returns how many lines were removed
+    pub fn removed(&self) -> usize {
+        self.lines()
+            .filter(|line| matches!(line, DiffLine::Removed { .. }))
+            .count()
+    }
+
+    /// returns true if the function did not change
+    pub const fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    fn lines(&self) -> impl Iterator<Item = &DiffLine> {
+        self.hunks.iter().flat_map(|hunk| hunk.lines.iter())
+    }
+}
+
+impl Display for FunctionDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut file = None;
+        for hunk in &self.hunks {
+            // each file starts with where its versions are from, as `<commit>:<file>`
+            if file != Some(&hunk.file) {
+                file = Some(&hunk.file);
+                match &self.old_commit {
+                    Some(old_commit) => writeln!(f, "--- {old_commit}:{}", hunk.file)?,
+                    None => writeln!(f, "--- /dev/null")?,
+                }
+                writeln!(f, "+++ {}:{}", self.new_commit, hunk.file)?;
+            }
+            write!(f, "{hunk}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A part of a `FunctionDiff` with some changed lines and the unchanged lines around them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// the path of the file the hunk is in
+    pub file: String,
+    /// the line number (in its file) where the hunk starts in the older version, 0 if it has no lines there
+    pub old_start: usize,
+    /// how many lines of the older version are in the hunk
+    pub old_len: usize,
+    /// the line number (in its file) where the hunk starts in the newer version, 0 if it has no lines there
+    pub new_start: usize,
+    /// how many lines of the newer version are in the hunk
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+impl Display for Hunk {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_len, self.new_start, self.new_len
+        )?;
+        for line in &self.lines {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A line in a `Hunk`, the line numbers are the line numbers in the file the function is in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    /// a line that is in both versions
+    Context {
+        old_line: usize,
+        new_line: usize,
+        text: String,
+    },
+    /// a line that was added in the newer version
+    Added { new_line: usize, text: String },
+    /// a line that was removed from the older version
+    Removed { old_line: usize, text: String },
+}
+
+impl Display for DiffLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Context { text, .. } => write!(f, " {text}"),
+            Self::Added { text, .. } => write!(f, "+{text}"),
+            Self::Removed { text, .. } => write!(f, "-{text}"),
+        }
+    }
+}
+
+/// Diffs two lists of lines (with their line numbers) into hunks.
+pub(crate) fn diff_lines(file: &str, old: &[(usize, &str)], new: &[(usize, &str)]) -> Vec<Hunk> {
+    let mut input = InternedInput::default();
+    input.update_before(old.iter().map(|(_, text)| *text));
+    input.update_after(new.iter().map(|(_, text)| *text));
+    let mut changes: Vec<Vec<(Range<usize>, Range<usize>)>> = vec![];
+    diff(
+        Algorithm::Histogram,
+        &input,
+        |before: Range<u32>, after: Range<u32>| {
+            let change = (
+                before.start as usize..before.end as usize,
+                after.start as usize..after.end as usize,
+            );
+            // changes that are close enough to share their context go in the same hunk
+            match changes.last_mut() {
+                Some(hunk)
+                    if hunk
+                        .last()
+                        .is_some_and(|last| change.0.start - last.0.end <= 2 * DIFF_CONTEXT) =>
+                {
+                    hunk.push(change);
+                }
+                _ => changes.push(vec![change]),
+            }
+        },
+    );
+    changes
+        .into_iter()
+        .filter_map(|hunk| {
+            let (first, last) = (hunk.first()?, hunk.last()?);
+            // the unchanged lines before (and after) a change are the same in both versions
+            let before = first.0.start.min(DIFF_CONTEXT);
+            let after = (old.len() - last.0.end).min(DIFF_CONTEXT);
+            let (mut old_pos, mut new_pos) = (first.0.start - before, first.1.start - before);
+            let (old_start, new_start) = (old_pos, new_pos);
+            let mut lines = vec![];
+            let context = |lines: &mut Vec<DiffLine>, old_pos: usize, new_pos: usize| {
+                lines.push(DiffLine::Context {
+                    old_line: old[old_pos].0,
+                    new_line: new[new_pos].0,
+                    text: old[old_pos].1.to_string(),
+                });
+            };
+            for (removed, added) in &hunk {
+                while old_pos < removed.start {
+                    context(&mut lines, old_pos, new_pos);
+                    old_pos += 1;
+                    new_pos += 1;
+                }
+                lines.extend(
+                    old[removed.clone()]
+                        .iter()
+                        .map(|(line, text)| DiffLine::Removed {
+                            old_line: *line,
+                            text: (*text).to_string(),
+                        }),
+                );
+                lines.extend(
+                    new[added.clone()]
+                        .iter()
+                        .map(|(line, text)| DiffLine::Added {
+                            new_line: *line,
+                            text: (*text).to_string(),
+                        }),
+                );
+                old_pos = removed.end;
+                new_pos = added.end;
+            }
+            for _ in 0..after {
+                context(&mut lines, old_pos, new_pos);
+                old_pos += 1;
+                new_pos += 1;
+            }
+            Some(Hunk {
+                file: file.to_string(),
+                old_start: old.get(old_start).map_or(0, |line| line.0),
+                old_len: old_pos - old_start,
+                new_start: new.get(new_start).map_or(0, |line| line.0),
+                new_len: new_pos - new_start,
+                lines,
+            })
+        })
+        .collect()
+}